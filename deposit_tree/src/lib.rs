@@ -125,6 +125,21 @@ impl DepositTree {
         Ok(deposits)
     }
 
+    pub fn root(&self) -> Result<H256> {
+        Self::validate_index_fits(self.deposit_count)?;
+
+        let deposit_count = self.deposit_count.try_into()?;
+        let root = self.merkle_tree.root(deposit_count);
+        Ok(ssz::mix_in_length(root, deposit_count))
+    }
+
+    /// Checks that the tree matches a trusted deposit root, e.g. one from a finalized `Eth1Data`.
+    pub fn verify_root(&self, expected: H256) -> Result<()> {
+        let actual = self.root()?;
+        ensure!(actual == expected, Error::RootMismatch { expected, actual });
+        Ok(())
+    }
+
     fn validate_index(&self, index: DepositIndex) -> Result<usize> {
         Self::validate_index_fits(index)?;
         self.validate_index_expected(index)?;
@@ -173,6 +188,8 @@ enum Error {
         data_count: usize,
         index_count: usize,
     },
+    #[error("deposit tree root {actual:?} does not match expected root {expected:?}")]
+    RootMismatch { expected: H256, actual: H256 },
 }
 
 // False positive. See <https://github.com/rust-lang/rust-clippy/issues/3307>.
@@ -233,6 +250,31 @@ mod tests {
             .expect_err("pushing with incorrect index should fail");
     }
 
+    #[test]
+    fn root_matches_push_and_compute_root() -> Result<()> {
+        let mut deposit_tree = DepositTree::default();
+
+        deposit_tree.verify_root(deposit_tree.root()?)?;
+
+        for index in 0..5 {
+            let data = DepositData {
+                amount: index,
+                ..DepositData::default()
+            };
+
+            let expected_root = deposit_tree.push_and_compute_root(index, data)?;
+
+            assert_eq!(deposit_tree.root()?, expected_root);
+            deposit_tree.verify_root(expected_root)?;
+        }
+
+        deposit_tree
+            .verify_root(H256::zero())
+            .expect_err("verifying against an incorrect root should fail");
+
+        Ok(())
+    }
+
     #[test]
     fn extend_and_construct_proofs_fails_on_empty_ranges() {
         let deposit_data = &[];
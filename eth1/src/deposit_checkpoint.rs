@@ -0,0 +1,271 @@
+use anyhow::Result;
+use deposit_tree::DepositTree;
+use eth1_api::Eth1Api;
+use types::phase0::primitives::{ExecutionBlockNumber, H256};
+
+/// A deposit tree obtained from a trusted source that has been checked against a deposit root.
+///
+/// A deposit count and root are not enough to continue building the tree.
+/// The checkpoint has to contain the frontier of the tree like the snapshots in EIP-4881.
+/// The tree returned by [`DepositCheckpoint::extend`] can be passed to
+/// [`Eth1Config::default_deposit_tree`](crate::Eth1Config) to avoid downloading older deposits.
+#[derive(Clone, Copy)]
+pub struct DepositCheckpoint {
+    deposit_tree: DepositTree,
+}
+
+impl DepositCheckpoint {
+    pub fn new(deposit_tree: DepositTree, deposit_root: H256) -> Result<Self> {
+        deposit_tree.verify_root(deposit_root)?;
+        Ok(Self { deposit_tree })
+    }
+
+    #[must_use]
+    pub const fn deposit_tree(self) -> DepositTree {
+        self.deposit_tree
+    }
+
+    /// Adds deposits made after the checkpoint up to and including `block_number`.
+    ///
+    /// Checkpoints are usually far behind the chain head, so deposit events are always
+    /// downloaded in chunks no longer than [`Eth1Api::with_max_deposit_range_span`].
+    ///
+    /// Fails if the downloaded deposits do not continue the checkpoint.
+    pub async fn extend(
+        self,
+        eth1_api: &Eth1Api,
+        block_number: ExecutionBlockNumber,
+    ) -> Result<DepositTree> {
+        let mut deposit_tree = self.deposit_tree;
+        let first_block_number = deposit_tree.last_added_block_number + 1;
+
+        if first_block_number <= block_number {
+            let deposit_events = eth1_api
+                .try_get_deposit_events(first_block_number..=block_number, None)
+                .await
+                .into_result()?;
+
+            for event in deposit_events.into_values().flatten() {
+                deposit_tree.push(event.index, event.data)?;
+            }
+
+            deposit_tree.last_added_block_number = block_number;
+        }
+
+        Ok(deposit_tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use httpmock::{Method, MockServer};
+    use reqwest::Client;
+    use serde_json::{json, Value};
+    use types::{
+        config::Config,
+        phase0::{
+            containers::DepositData,
+            primitives::{DepositIndex, Gwei},
+        },
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn extend_adds_deposits_made_after_checkpoint() -> Result<()> {
+        let server = MockServer::start();
+
+        let logs_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs")
+                .body_contains(r#""fromBlock":"0xb""#)
+                .body_contains(r#""toBlock":"0x14""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [deposit_log("0xc", 2, 3)],
+                })
+                .to_string(),
+            );
+        });
+
+        let mut full_tree = DepositTree::default();
+
+        for index in 0..2 {
+            full_tree.push(index, deposit_data(index + 1))?;
+        }
+
+        let checkpoint_tree = DepositTree {
+            last_added_block_number: 10,
+            ..full_tree
+        };
+
+        let expected_root = full_tree.push_and_compute_root(2, deposit_data(3))?;
+
+        let checkpoint = DepositCheckpoint::new(checkpoint_tree, checkpoint_tree.root()?)?;
+        let deposit_tree = checkpoint.extend(&eth1_api(&server)?, 20).await?;
+
+        logs_mock.assert_hits(1);
+
+        assert_eq!(deposit_tree.deposit_count, 3);
+        assert_eq!(deposit_tree.last_added_block_number, 20);
+
+        deposit_tree.verify_root(expected_root)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn extend_splits_gaps_longer_than_max_deposit_range_span() -> Result<()> {
+        let server = MockServer::start();
+
+        let first_chunk_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs")
+                .body_contains(r#""fromBlock":"0xb""#)
+                .body_contains(r#""toBlock":"0xf""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [deposit_log("0xc", 2, 3)],
+                })
+                .to_string(),
+            );
+        });
+
+        let second_chunk_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs")
+                .body_contains(r#""fromBlock":"0x10""#)
+                .body_contains(r#""toBlock":"0x14""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [deposit_log("0x13", 3, 4)],
+                })
+                .to_string(),
+            );
+        });
+
+        let mut full_tree = DepositTree::default();
+
+        for index in 0..2 {
+            full_tree.push(index, deposit_data(index + 1))?;
+        }
+
+        let checkpoint_tree = DepositTree {
+            last_added_block_number: 10,
+            ..full_tree
+        };
+
+        full_tree.push(2, deposit_data(3))?;
+
+        let expected_root = full_tree.push_and_compute_root(3, deposit_data(4))?;
+
+        // Deposit events for blocks 11 to 20 do not fit in a single request.
+        let eth1_api = eth1_api(&server)?.with_max_deposit_range_span(5);
+
+        let checkpoint = DepositCheckpoint::new(checkpoint_tree, checkpoint_tree.root()?)?;
+        let deposit_tree = checkpoint.extend(&eth1_api, 20).await?;
+
+        first_chunk_mock.assert_hits(1);
+        second_chunk_mock.assert_hits(1);
+
+        assert_eq!(deposit_tree.deposit_count, 4);
+        assert_eq!(deposit_tree.last_added_block_number, 20);
+
+        deposit_tree.verify_root(expected_root)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn extend_fails_when_deposits_do_not_continue_checkpoint() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [deposit_log("0xc", 3, 4)],
+                })
+                .to_string(),
+            );
+        });
+
+        let mut checkpoint_tree = DepositTree::default();
+
+        for index in 0..2 {
+            checkpoint_tree.push(index, deposit_data(index + 1))?;
+        }
+
+        DepositCheckpoint::new(checkpoint_tree, checkpoint_tree.root()?)?
+            .extend(&eth1_api(&server)?, 20)
+            .await
+            .err()
+            .expect("deposit with index 3 should not follow deposit with index 1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn new_fails_on_root_mismatch() {
+        DepositCheckpoint::new(DepositTree::default(), H256::zero())
+            .err()
+            .expect("checkpoint with incorrect root should be rejected");
+    }
+
+    fn eth1_api(server: &MockServer) -> Result<Eth1Api> {
+        Ok(Eth1Api::new(
+            Arc::new(Config::mainnet()),
+            Client::new(),
+            Arc::default(),
+            vec![server.url("/").parse()?],
+            None,
+            None,
+        ))
+    }
+
+    fn deposit_data(amount: Gwei) -> DepositData {
+        DepositData {
+            amount,
+            ..DepositData::default()
+        }
+    }
+
+    fn deposit_log(block_number: &str, index: DepositIndex, amount: Gwei) -> Value {
+        // A `DepositEvent` log takes up 18 EVM words.
+        // The amount is in the 12th one and the index is in the 18th one.
+        let mut data = [0; 18 * 32];
+        data[11 * 32..11 * 32 + 8].copy_from_slice(&amount.to_le_bytes());
+        data[17 * 32..17 * 32 + 8].copy_from_slice(&index.to_le_bytes());
+
+        json!({
+            "address": Config::mainnet().deposit_contract_address,
+            "topics": [eth1_api::DepositEvent::TOPIC],
+            "data": format!("0x{}", hex::encode(data)),
+            "blockHash": H256::repeat_byte(1),
+            "blockNumber": block_number,
+            "transactionHash": H256::repeat_byte(3),
+            "transactionIndex": "0x0",
+            "logIndex": "0x0",
+            "removed": false,
+        })
+    }
+}
@@ -159,7 +159,10 @@ impl DownloadManager {
 
             info!("downloading Eth1 deposits from block {from_block} to block {to_block}");
 
-            let deposit_event_map = self.api.get_deposit_events(from_block..=to_block).await?;
+            let deposit_event_map = self
+                .api
+                .get_deposit_events(from_block..=to_block, None)
+                .await?;
             let deposit_events = deposit_event_map.values().flatten().collect();
 
             if let Err(error) = self.cache.add_deposits(deposit_events, to_block) {
@@ -186,8 +189,11 @@ impl DownloadManager {
         if let Some(block_num) = self.eth1_config.deposit_contract_starting_block {
             Ok(block_num)
         } else {
+            let contract_address = self.chain_config.deposit_contract_address;
+            let deploy_block = self.chain_config.deposit_contract_deploy_block;
+
             self.api
-                .get_first_deposit_contract_block_number()
+                .get_first_deposit_contract_block_number(Some(contract_address), Some(deploy_block))
                 .await
                 .context(Error::ConnectionError)?
                 .ok_or(Error::DepositContractNotFound)
@@ -230,3 +236,69 @@ impl DownloadManager {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use database::Database;
+    use httpmock::{Method, MockServer};
+    use serde_json::json;
+    use types::phase0::primitives::H256;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn deposit_contract_is_searched_for_from_configured_deploy_block() -> Result<()> {
+        let server = MockServer::start();
+
+        let chain_config = Arc::new(ChainConfig {
+            deposit_contract_deploy_block: 1000,
+            ..ChainConfig::mainnet()
+        });
+
+        let logs_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs")
+                .body_contains(r#""fromBlock":"0x3e8""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [{
+                        "address": chain_config.deposit_contract_address,
+                        "topics": [eth1_api::DepositEvent::TOPIC],
+                        "data": "0x",
+                        "blockHash": H256::repeat_byte(1),
+                        "blockNumber": "0x3ed",
+                        "transactionHash": H256::repeat_byte(3),
+                        "transactionIndex": "0x0",
+                        "logIndex": "0x0",
+                        "removed": false,
+                    }],
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_config = Arc::new(Eth1Config {
+            eth1_rpc_urls: vec![server.url("/").parse()?],
+            ..Eth1Config::default()
+        });
+
+        let cache = Arc::new(Eth1Cache::new(Database::in_memory(), None)?);
+        let download_manager =
+            DownloadManager::new(chain_config, eth1_config, Client::new(), cache, None, None);
+
+        assert_eq!(
+            download_manager
+                .earliest_downloadable_block_number()
+                .await?,
+            1005,
+        );
+
+        logs_mock.assert_hits(1);
+
+        Ok(())
+    }
+}
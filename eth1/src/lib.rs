@@ -1,12 +1,14 @@
 pub use eth1_api::{DepositEvent, Eth1Block};
 
 pub use crate::{
+    deposit_checkpoint::DepositCheckpoint,
     eth1_chain::{Eth1Chain, Eth1Config},
     genesis::wait as wait_for_genesis,
 };
 
 use crate::eth1_cache::Eth1Cache;
 
+mod deposit_checkpoint;
 mod download_manager;
 mod eth1_cache;
 mod eth1_chain;
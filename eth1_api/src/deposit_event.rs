@@ -94,8 +94,8 @@ struct RawDepositEvent {
 
 assert_eq_size!(RawDepositEvent, [EvmWord; 18]);
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize, Ssz)]
-#[cfg_attr(test, derive(PartialEq, Eq, Default))]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, Ssz)]
+#[cfg_attr(any(test, feature = "test-util"), derive(Default))]
 #[ssz(derive_hash = false)]
 pub struct DepositEvent {
     pub data: DepositData,
@@ -167,6 +167,34 @@ impl DepositEvent {
     ));
 
     const LENGTH: usize = core::mem::size_of::<RawDepositEvent>();
+
+    #[cfg(any(test, feature = "test-util"))]
+    #[must_use]
+    pub const fn with_index(mut self, index: DepositIndex) -> Self {
+        self.index = index;
+        self
+    }
+
+    #[cfg(any(test, feature = "test-util"))]
+    #[must_use]
+    pub const fn with_pubkey(mut self, pubkey: PublicKeyBytes) -> Self {
+        self.data.pubkey = pubkey;
+        self
+    }
+
+    #[cfg(any(test, feature = "test-util"))]
+    #[must_use]
+    pub const fn with_withdrawal_credentials(mut self, withdrawal_credentials: H256) -> Self {
+        self.data.withdrawal_credentials = withdrawal_credentials;
+        self
+    }
+
+    #[cfg(any(test, feature = "test-util"))]
+    #[must_use]
+    pub const fn with_amount(mut self, amount: Gwei) -> Self {
+        self.data.amount = amount;
+        self
+    }
 }
 
 #[allow(clippy::default_trait_access)]
@@ -0,0 +1,1739 @@
+use core::{
+    ops::RangeInclusive,
+    sync::atomic::{AtomicBool, AtomicU64},
+    time::Duration,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex as StdMutex},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, ensure, Error as AnyhowError, Result};
+use execution_engine::{PayloadId, WithdrawalV1};
+use futures::{channel::mpsc::UnboundedSender, future, lock::Mutex};
+use log::warn;
+use prometheus_metrics::Metrics;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use types::{
+    capella::{containers::Withdrawal, primitives::WithdrawalIndex},
+    config::Config,
+    nonstandard::Phase,
+    phase0::primitives::{
+        ExecutionAddress, ExecutionBlockHash, ExecutionBlockNumber, ExecutionTransactionHash,
+        UnixSeconds,
+    },
+};
+use web3::{
+    api::Namespace as _,
+    helpers::CallFuture,
+    types::{Block, BlockId, BlockNumber, Bytes, TransactionReceipt, U256, U64},
+    Error as Web3Error,
+};
+
+#[cfg(feature = "optimism")]
+use crate::eth1_api::engine::OptimismPayloadAttributes;
+use crate::{
+    auth::Auth,
+    eth1_api::{
+        endpoints::{EndpointSelector, ErrorRecord, InOrderEndpointSelector},
+        engine::{EngineCallObserver, EngineCallRecord, PayloadAttributesError},
+        transport::{truncate_body, InFlightCall, InFlightCallKey, RequestReport},
+    },
+    eth1_block::{self, Eth1Block},
+    Eth1ApiToMetrics,
+};
+
+// High enough to not limit any realistic workload unless overridden.
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 1024;
+
+// Callers like `eth1::DownloadManager` request far smaller ranges.
+// This only guards against accidental requests that would be expensive for providers.
+const DEFAULT_MAX_DEPOSIT_RANGE_SPAN: u64 = 100_000;
+
+// Enough to smooth out uneven batch sizes without lagging far behind changes in throughput.
+const MAX_SYNC_PROGRESS_SAMPLES: usize = 16;
+
+// The latest execution block is normally at most a slot old.
+// A much larger difference means that a clock is wrong or the execution client is not synced.
+const CLOCK_SKEW_WARNING_THRESHOLD: Duration = Duration::from_secs(60);
+
+/// Returned by [`Eth1Api::sync_progress`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SyncProgress {
+    pub head_number: ExecutionBlockNumber,
+    pub blocks_remaining: u64,
+    /// Extrapolated from previous calls to [`Eth1Api::sync_progress`].
+    /// `None` until there are enough of them to measure the rate at which blocks are processed.
+    pub estimated_time_remaining: Option<Duration>,
+}
+
+#[allow(clippy::module_name_repetitions, clippy::struct_field_names)]
+pub struct Eth1Api {
+    pub(crate) config: Arc<Config>,
+    pub(crate) client: Client,
+    pub(crate) auth: Arc<Auth>,
+    pub(crate) original: Vec<Url>,
+    // Endpoints that have not failed or been demoted since the last reset.
+    // Endpoints are only reset to `original` after all of them fail.
+    // Related calls (like `engine_forkchoiceUpdated` followed by `engine_getPayload`) may still
+    // move between endpoints if one is demoted or `endpoint_selector` picks another one.
+    // `endpoint_stickiness` prevents that for a while after each switch.
+    pub(crate) endpoints: Mutex<Vec<Url>>,
+    pub(crate) endpoint_selector: Arc<dyn EndpointSelector>,
+    pub(crate) eth1_api_to_metrics_tx: Option<UnboundedSender<Eth1ApiToMetrics>>,
+    // Set once sending to `eth1_api_to_metrics_tx` fails.
+    // A closed channel never reopens, so there is no point in trying again.
+    pub(crate) metrics_channel_closed: AtomicBool,
+    pub(crate) endpoint_switches: AtomicU64,
+    // Bounds the number of requests in flight across all callers.
+    // Hosted providers tend to reject requests above some concurrency limit.
+    pub(crate) request_permits: Semaphore,
+    // How long to wait before trying all endpoints once more after every one of them has failed.
+    pub(crate) exhaustion_grace: Option<Duration>,
+    // How long to wait before retrying an endpoint that responded with `429 Too Many Requests`.
+    // Switching endpoints does not help if all of them belong to the same provider.
+    pub(crate) rate_limit_backoff: Option<Duration>,
+    // Delays from `Retry-After` headers of `429 Too Many Requests` responses not acted upon yet.
+    // Take precedence over `rate_limit_backoff`. Only recorded for compressed requests.
+    // Other requests are made through `web3`, which does not expose response headers.
+    pub(crate) retry_after_hints: StdMutex<HashMap<Url, Duration>>,
+    // Endpoints with a 95th percentile latency above this are demoted like failing ones.
+    pub(crate) slow_endpoint_threshold: Option<Duration>,
+    // How long an endpoint stays selected after it starts serving requests unless it fails.
+    pub(crate) endpoint_stickiness: Option<Duration>,
+    // The endpoint that served the latest request and when it started serving requests.
+    // A synchronous mutex because it is never held across an `.await`.
+    pub(crate) sticky_endpoint: StdMutex<Option<(Url, Instant)>>,
+    pub(crate) endpoint_latencies: Mutex<HashMap<Url, VecDeque<Duration>>>,
+    pub(crate) recent_errors: Mutex<HashMap<Url, VecDeque<ErrorRecord>>>,
+    // Later phases make execution clients do more work per payload (e.g. blob validation).
+    // Phases not present here use `ENGINE_NEW_PAYLOAD_TIMEOUT`.
+    pub(crate) new_payload_timeouts: BTreeMap<Phase, Duration>,
+    pub(crate) max_deposit_range_span: u64,
+    // Whether ranges longer than `max_deposit_range_span` are split up instead of rejected.
+    pub(crate) chunk_deposit_ranges: bool,
+    // Whether deposit events from logs are checked against block receipts.
+    pub(crate) verify_deposits_with_receipts: bool,
+    pub(crate) engine_call_observer: Option<Arc<dyn EngineCallObserver>>,
+    // Engine API calls are not recorded if this is 0.
+    pub(crate) recent_engine_call_capacity: usize,
+    pub(crate) recent_engine_calls: Mutex<VecDeque<EngineCallRecord>>,
+    // Whether `Eth1Api::get_blocks` checks that the blocks it returns form a chain.
+    verify_block_chaining: bool,
+    // Whether `Eth1Api::get_block_withdrawals` checks that withdrawal indices are consecutive.
+    verify_withdrawal_indices: bool,
+    // Whether `Eth1Api::get_blocks` fails if a block in the range is missing.
+    reject_missing_blocks: bool,
+    // Whether `Eth1Api::get_payload` is sent to the endpoint that issued the payload ID.
+    pub(crate) pin_payload_endpoints: bool,
+    pub(crate) pinned_payload_endpoints: Mutex<VecDeque<(PayloadId, Url)>>,
+    // Whether `Eth1Api::new_payload` fails on `INVALID` statuses.
+    pub(crate) fatal_invalid_payloads: bool,
+    // Whether identical Engine API calls made concurrently share a single request.
+    pub(crate) coalesce_engine_calls: bool,
+    // A synchronous mutex so that `InFlightCallGuard` can remove entries when dropped.
+    // It is never held across an `.await`.
+    pub(crate) in_flight_engine_calls: StdMutex<HashMap<InFlightCallKey, InFlightCall>>,
+    // Whether Engine API request bodies are compressed with gzip.
+    pub(crate) compress_engine_requests: bool,
+    // Endpoints that rejected a compressed request. They are only sent uncompressed ones.
+    pub(crate) uncompressed_endpoints: StdMutex<HashSet<Url>>,
+    // Set for nodes that are not meant to use an Eth1 RPC at all. See `Eth1Api::sync_only`.
+    pub(crate) sync_only: bool,
+    // Processed block numbers passed to `Eth1Api::sync_progress` along with when they were passed.
+    sync_progress_samples: Mutex<VecDeque<(Instant, ExecutionBlockNumber)>>,
+    pub(crate) metrics: Option<Arc<Metrics>>,
+    #[cfg(feature = "optimism")]
+    pub(crate) optimism_payload_attributes: Option<OptimismPayloadAttributes>,
+}
+
+impl Eth1Api {
+    #[must_use]
+    pub fn new(
+        config: Arc<Config>,
+        client: Client,
+        auth: Arc<Auth>,
+        eth1_rpc_urls: Vec<Url>,
+        eth1_api_to_metrics_tx: Option<UnboundedSender<Eth1ApiToMetrics>>,
+        metrics: Option<Arc<Metrics>>,
+    ) -> Self {
+        Self {
+            config,
+            client,
+            auth,
+            original: eth1_rpc_urls.clone(),
+            endpoints: Mutex::new(eth1_rpc_urls),
+            endpoint_selector: Arc::new(InOrderEndpointSelector),
+            eth1_api_to_metrics_tx,
+            metrics_channel_closed: AtomicBool::new(false),
+            endpoint_switches: AtomicU64::new(0),
+            request_permits: Semaphore::new(DEFAULT_MAX_CONCURRENT_REQUESTS),
+            exhaustion_grace: None,
+            rate_limit_backoff: None,
+            retry_after_hints: StdMutex::new(HashMap::new()),
+            slow_endpoint_threshold: None,
+            endpoint_stickiness: None,
+            sticky_endpoint: StdMutex::new(None),
+            endpoint_latencies: Mutex::new(HashMap::new()),
+            recent_errors: Mutex::new(HashMap::new()),
+            new_payload_timeouts: BTreeMap::new(),
+            max_deposit_range_span: DEFAULT_MAX_DEPOSIT_RANGE_SPAN,
+            chunk_deposit_ranges: false,
+            verify_deposits_with_receipts: false,
+            engine_call_observer: None,
+            recent_engine_call_capacity: 0,
+            recent_engine_calls: Mutex::new(VecDeque::new()),
+            verify_block_chaining: false,
+            verify_withdrawal_indices: false,
+            reject_missing_blocks: false,
+            pin_payload_endpoints: false,
+            pinned_payload_endpoints: Mutex::new(VecDeque::new()),
+            fatal_invalid_payloads: false,
+            coalesce_engine_calls: false,
+            in_flight_engine_calls: StdMutex::new(HashMap::new()),
+            compress_engine_requests: false,
+            uncompressed_endpoints: StdMutex::new(HashSet::new()),
+            sync_only: false,
+            sync_progress_samples: Mutex::new(VecDeque::new()),
+            metrics,
+            #[cfg(feature = "optimism")]
+            optimism_payload_attributes: None,
+        }
+    }
+
+    /// Creates an `Eth1Api` without endpoints for nodes that sync without proposing blocks.
+    ///
+    /// Such nodes do not need an Eth1 RPC (except during the Merge transition).
+    /// Every request fails with an error saying so rather than one about exhausted endpoints.
+    #[must_use]
+    pub fn sync_only(config: Arc<Config>, metrics: Option<Arc<Metrics>>) -> Self {
+        Self {
+            sync_only: true,
+            ..Self::new(config, Client::new(), Arc::default(), vec![], None, metrics)
+        }
+    }
+
+    #[must_use]
+    pub fn with_max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.request_permits = Semaphore::new(max_concurrent_requests);
+        self
+    }
+
+    /// Makes requests switch to the next endpoint once the 95th percentile latency of the current
+    /// one exceeds `threshold`, even if it does not return errors.
+    ///
+    /// An endpoint that always answers slowly delays proposals more than a fast fallback would.
+    /// The switch is not made if the next endpoint is known to be slow as well.
+    /// Demoted endpoints are tried again after all endpoints have failed, as with errors.
+    #[must_use]
+    pub const fn with_slow_endpoint_demotion(mut self, threshold: Duration) -> Self {
+        self.slow_endpoint_threshold = Some(threshold);
+        self
+    }
+
+    /// Keeps sending requests to an endpoint for `window` after it serves one.
+    ///
+    /// This keeps related calls (e.g. `engine_forkchoiceUpdated` followed by
+    /// `engine_getPayload`) on the same execution client, which may otherwise see a slightly
+    /// different chain. Within the window, slow endpoints are not demoted and the
+    /// [`EndpointSelector`] is not consulted. Failing endpoints are still switched away from.
+    #[must_use]
+    pub const fn with_endpoint_stickiness(mut self, window: Duration) -> Self {
+        self.endpoint_stickiness = Some(window);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_exhaustion_grace(mut self, exhaustion_grace: Duration) -> Self {
+        self.exhaustion_grace = Some(exhaustion_grace);
+        self
+    }
+
+    /// Makes requests rejected with `429 Too Many Requests` wait for `backoff` and retry the same
+    /// endpoint (a few times at most) instead of switching to the next one.
+    ///
+    /// Rejections of compressed Engine API requests (see
+    /// [`Eth1Api::with_engine_request_compression`]) with a `Retry-After` header are retried after
+    /// the delay in the header even without this. `backoff` is used for all other rejections.
+    /// Headers of responses to other requests are not available.
+    #[must_use]
+    pub const fn with_rate_limit_backoff(mut self, backoff: Duration) -> Self {
+        self.rate_limit_backoff = Some(backoff);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_deposit_range_span(mut self, max_deposit_range_span: u64) -> Self {
+        self.max_deposit_range_span = max_deposit_range_span;
+        self
+    }
+
+    /// Makes [`Eth1Api::get_deposit_events`] split ranges longer than the maximum span into
+    /// multiple requests instead of rejecting them.
+    #[must_use]
+    pub const fn with_deposit_range_chunking(mut self) -> Self {
+        self.chunk_deposit_ranges = true;
+        self
+    }
+
+    /// Makes [`Eth1Api::get_deposit_events`] compare the deposit events for every block with the
+    /// logs in the receipts of the block and use the latter if they differ.
+    ///
+    /// Some execution clients silently omit logs from `eth_getLogs` responses under load.
+    /// Receipts are requested with `eth_getBlockReceipts` once per block in the range,
+    /// so this is only practical when following the chain rather than syncing deposits.
+    #[must_use]
+    pub const fn with_deposit_receipt_verification(mut self) -> Self {
+        self.verify_deposits_with_receipts = true;
+        self
+    }
+
+    #[must_use]
+    pub fn with_endpoint_selector(mut self, selector: Arc<dyn EndpointSelector>) -> Self {
+        self.endpoint_selector = selector;
+        self
+    }
+
+    #[must_use]
+    pub fn with_engine_call_observer(mut self, observer: Arc<dyn EngineCallObserver>) -> Self {
+        self.engine_call_observer = Some(observer);
+        self
+    }
+
+    /// Makes [`Eth1Api`] remember the last `capacity` Engine API calls.
+    ///
+    /// See [`Eth1Api::recent_engine_calls`].
+    #[must_use]
+    pub const fn with_recent_engine_calls(mut self, capacity: usize) -> Self {
+        self.recent_engine_call_capacity = capacity;
+        self
+    }
+
+    /// Makes [`Eth1Api::get_blocks`] check that each block it returns is the parent of the next.
+    ///
+    /// This catches endpoints serving inconsistent data and reorgs that happen mid-request.
+    #[must_use]
+    pub const fn with_block_chaining_verification(mut self) -> Self {
+        self.verify_block_chaining = true;
+        self
+    }
+
+    /// Makes [`Eth1Api::get_block_withdrawals`] check that withdrawal indices in each block are
+    /// consecutive.
+    ///
+    /// Indices in valid blocks always are. This catches bugs in execution clients early.
+    #[must_use]
+    pub const fn with_withdrawal_index_verification(mut self) -> Self {
+        self.verify_withdrawal_indices = true;
+        self
+    }
+
+    /// Makes [`Eth1Api::get_blocks`] fail if any block in the range is missing instead of
+    /// skipping it.
+    ///
+    /// Execution clients that prune old blocks may not have all of them.
+    /// Deposit processing cannot continue past a gap because deposit indices would not line up.
+    #[must_use]
+    pub const fn with_missing_block_rejection(mut self) -> Self {
+        self.reject_missing_blocks = true;
+        self
+    }
+
+    /// Makes [`Eth1Api::get_payload`] request payloads from the endpoint that returned their IDs
+    /// in response to [`Eth1Api::forkchoice_updated`].
+    ///
+    /// Payload IDs are only meaningful to the execution client that issued them.
+    /// If that endpoint fails, the error is returned instead of trying other endpoints.
+    #[must_use]
+    pub const fn with_payload_endpoint_pinning(mut self) -> Self {
+        self.pin_payload_endpoints = true;
+        self
+    }
+
+    /// Makes [`Eth1Api::new_payload`] fail with a distinct error when the execution client
+    /// responds with an `INVALID` status.
+    ///
+    /// Meant for debugging bad block incidents. [`ExecutionService`](crate::ExecutionService)
+    /// stops when it gets the error, so the node halts before fork choice marks the payload as
+    /// invalid and moves on, preserving the state the block was applied to.
+    #[must_use]
+    pub const fn with_fatal_invalid_payloads(mut self) -> Self {
+        self.fatal_invalid_payloads = true;
+        self
+    }
+
+    /// Makes concurrent Engine API calls with identical methods and parameters share one request.
+    ///
+    /// This avoids sending the same payload to the execution client several times when multiple
+    /// components validate it at once. Only successful responses are shared.
+    /// If the shared request fails, each caller sends its own request.
+    #[must_use]
+    pub const fn with_engine_call_coalescing(mut self) -> Self {
+        self.coalesce_engine_calls = true;
+        self
+    }
+
+    /// Makes Engine API requests send bodies compressed with gzip.
+    ///
+    /// This saves bandwidth with remote execution clients for large payloads (e.g. ones with
+    /// many blobs). An endpoint that rejects a compressed request with `415 Unsupported Media Type`
+    /// or an error about `Content-Encoding` is sent uncompressed requests from then on.
+    #[must_use]
+    pub const fn with_engine_request_compression(mut self) -> Self {
+        self.compress_engine_requests = true;
+        self
+    }
+
+    #[must_use]
+    pub fn with_new_payload_timeout(mut self, phase: Phase, timeout: Duration) -> Self {
+        self.new_payload_timeouts.insert(phase, timeout);
+        self
+    }
+
+    #[cfg(feature = "optimism")]
+    #[must_use]
+    pub fn with_optimism_payload_attributes(
+        mut self,
+        optimism_payload_attributes: OptimismPayloadAttributes,
+    ) -> Self {
+        self.optimism_payload_attributes = Some(optimism_payload_attributes);
+        self
+    }
+
+    pub async fn current_head_number(&self) -> Result<ExecutionBlockNumber> {
+        Ok(self
+            .request_with_fallback(|(api, headers)| Ok(api.block_number(headers)))
+            .await?
+            .as_u64())
+    }
+
+    /// Returns the numbers of the current head and the latest finalized block.
+    ///
+    /// Both are requested concurrently from the same endpoint. This takes about as long as
+    /// [`Eth1Api::current_head_number`] and avoids mixing numbers from different endpoints.
+    pub async fn head_and_finalized(&self) -> Result<(ExecutionBlockNumber, ExecutionBlockNumber)> {
+        let (head_number, finalized_block): (U64, Value) = self
+            .request_with_fallback_counting_endpoints(
+                |(url, api, headers)| {
+                    let (method, params) = block_request(BlockNumber::Finalized.into())?;
+
+                    let head_number = self.send_request(
+                        api.transport(),
+                        url,
+                        "eth_blockNumber",
+                        vec![],
+                        headers.clone(),
+                        None,
+                    );
+
+                    let finalized_block =
+                        self.send_request(api.transport(), url, method, params, headers, None);
+
+                    Ok(CallFuture::new(async move {
+                        let (head_number, finalized_block) =
+                            future::try_join(head_number, finalized_block).await?;
+
+                        Ok::<_, Web3Error>(Value::Array(vec![head_number, finalized_block]))
+                    }))
+                },
+                &mut RequestReport::default(),
+            )
+            .await?;
+
+        let Some(finalized_block) = parse_block(finalized_block)? else {
+            bail!(Error::BlockTagNotSupported {
+                tag: BlockNumber::Finalized,
+            });
+        };
+
+        Ok((head_number.as_u64(), finalized_block.number))
+    }
+
+    /// Compares `processed` with the current head and estimates how long it will take to catch up.
+    ///
+    /// Meant to be called periodically with the number of the latest block processed by the
+    /// caller. The rate is measured over the last few calls.
+    pub async fn sync_progress(&self, processed: ExecutionBlockNumber) -> Result<SyncProgress> {
+        let head_number = self.current_head_number().await?;
+        let blocks_remaining = head_number.saturating_sub(processed);
+
+        let mut samples = self.sync_progress_samples.lock().await;
+
+        // Processing went backwards (most likely due to a restart). Old samples are meaningless.
+        if samples
+            .back()
+            .is_some_and(|(_, previous)| *previous > processed)
+        {
+            samples.clear();
+        }
+
+        if samples.len() == MAX_SYNC_PROGRESS_SAMPLES {
+            samples.pop_front();
+        }
+
+        samples.push_back((Instant::now(), processed));
+
+        let estimated_time_remaining = samples.front().zip(samples.back()).and_then(
+            |((oldest_instant, oldest), (newest_instant, newest))| {
+                let blocks_processed = newest - oldest;
+                let elapsed = newest_instant.duration_since(*oldest_instant);
+
+                if blocks_processed == 0 || elapsed.is_zero() {
+                    return None;
+                }
+
+                #[allow(clippy::cast_precision_loss)]
+                let seconds_per_block = elapsed.as_secs_f64() / blocks_processed as f64;
+
+                #[allow(clippy::cast_precision_loss)]
+                Duration::try_from_secs_f64(seconds_per_block * blocks_remaining as f64).ok()
+            },
+        );
+
+        Ok(SyncProgress {
+            head_number,
+            blocks_remaining,
+            estimated_time_remaining,
+        })
+    }
+
+    /// Quantities in the block may be encoded as decimal strings or JSON numbers in addition to
+    /// prefixed hexadecimal strings required by the Ethereum JSON-RPC specification.
+    pub async fn get_block(&self, block_id: BlockId) -> Result<Option<Eth1Block>> {
+        let (method, params) = block_request(block_id)?;
+
+        parse_block(self.execute(method, params, None).await?)
+    }
+
+    /// Returns the number of transactions in a block without fetching the block itself.
+    ///
+    /// Calls `eth_getBlockTransactionCountByHash` or `eth_getBlockTransactionCountByNumber`
+    /// depending on `block_id`.
+    pub async fn get_block_transaction_count(&self, block_id: BlockId) -> Result<Option<u64>> {
+        self.request_with_fallback(|(api, headers)| {
+            Ok(api.block_transaction_count(block_id, headers))
+        })
+        .await?
+        // `<U256 as TryInto<u64>>::Error` is `&'static str`.
+        .map(|count| count.try_into().map_err(AnyhowError::msg))
+        .transpose()
+    }
+
+    /// Returns only the withdrawals in a block.
+    ///
+    /// Returns `None` if the block is unknown or predates Capella.
+    /// Fails if withdrawal indices in the block are not consecutive and
+    /// [`Eth1Api::with_withdrawal_index_verification`] has been called.
+    pub async fn get_block_withdrawals(
+        &self,
+        block_id: BlockId,
+    ) -> Result<Option<Vec<Withdrawal>>> {
+        let (method, params) = block_request(block_id)?;
+
+        let block = self
+            .execute::<Option<RawBlockWithdrawals>>(method, params, None)
+            .await?;
+
+        let Some(withdrawals) = block.and_then(|block| block.withdrawals) else {
+            return Ok(None);
+        };
+
+        if self.verify_withdrawal_indices {
+            for (previous, withdrawal) in withdrawals.iter().zip(withdrawals.iter().skip(1)) {
+                // The indices come from the execution client and may be arbitrarily large.
+                ensure!(
+                    previous.index.checked_add(1) == Some(withdrawal.index),
+                    Error::WithdrawalIndicesNotConsecutive {
+                        previous_index: previous.index,
+                        index: withdrawal.index,
+                    },
+                );
+            }
+        }
+
+        Ok(Some(withdrawals.into_iter().map(Into::into).collect()))
+    }
+
+    /// Returns the balance of `address` in wei as of `block`.
+    ///
+    /// Comparing the balance of a fee recipient before and after a proposed block shows whether
+    /// the execution layer rewards were received.
+    pub async fn get_balance(&self, address: ExecutionAddress, block: BlockNumber) -> Result<U256> {
+        self.request_with_fallback(|(api, headers)| Ok(api.balance(address, Some(block), headers)))
+            .await
+    }
+
+    /// Returns the receipt of the transaction with hash `transaction_hash`.
+    ///
+    /// Returns `None` if the transaction is unknown or still pending.
+    /// The receipt of a deposit transaction shows whether it succeeded and contains its logs.
+    pub async fn get_transaction_receipt(
+        &self,
+        transaction_hash: ExecutionTransactionHash,
+    ) -> Result<Option<TransactionReceipt>> {
+        self.request_with_fallback(|(api, headers)| {
+            Ok(api.transaction_receipt(transaction_hash, headers))
+        })
+        .await
+    }
+
+    /// Returns the receipts of all transactions in the block with number `block_number`.
+    ///
+    /// Returns `None` if the block is unknown.
+    /// Uses `eth_getBlockReceipts`, which is not part of the standard JSON-RPC API but is
+    /// supported by all major execution clients.
+    pub async fn get_block_receipts(
+        &self,
+        block_number: ExecutionBlockNumber,
+    ) -> Result<Option<Vec<TransactionReceipt>>> {
+        let params = vec![serde_json::to_value(U64::from(block_number))?];
+
+        self.request_with_fallback_counting_endpoints(
+            |(url, api, headers)| {
+                Ok(CallFuture::new(self.send_request(
+                    api.transport(),
+                    url,
+                    "eth_getBlockReceipts",
+                    params.clone(),
+                    headers,
+                    None,
+                )))
+            },
+            &mut RequestReport::default(),
+        )
+        .await
+    }
+
+    pub async fn get_block_by_number(
+        &self,
+        block_number: ExecutionBlockNumber,
+    ) -> Result<Option<Eth1Block>> {
+        self.get_block(U64::from(block_number).into()).await
+    }
+
+    pub async fn get_block_by_hash(
+        &self,
+        block_hash: ExecutionBlockHash,
+    ) -> Result<Option<Eth1Block>> {
+        self.get_block(block_hash.into()).await
+    }
+
+    /// Returns the latest block that the execution client considers finalized.
+    ///
+    /// The `finalized` and `safe` block tags are only meaningful after the Merge.
+    /// Execution clients that have not gone through it respond with `null`.
+    pub async fn get_finalized_block(&self) -> Result<Eth1Block> {
+        self.get_block_by_tag(BlockNumber::Finalized).await
+    }
+
+    /// Returns the block that the execution client is building from its mempool.
+    ///
+    /// Meant for MEV and fee analytics. Returns `None` if the execution client does not maintain
+    /// a pending block. The hash of the returned block is zero because it is not sealed yet.
+    pub async fn get_pending_block(&self) -> Result<Option<Eth1Block>> {
+        let (method, params) = block_request(BlockNumber::Pending.into())?;
+
+        parse_raw_block(self.execute(method, params, None).await?)?
+            .map(eth1_block::try_from_pending_block)
+            .transpose()
+    }
+
+    /// Returns the difference between the timestamp of the latest execution block and local time.
+    ///
+    /// Logs a warning if the difference is larger than a minute. Payload attributes with
+    /// timestamps computed from a skewed clock may be rejected when proposing.
+    pub async fn check_clock_skew(&self) -> Result<Duration> {
+        let latest_block = self.get_block_by_tag(BlockNumber::Latest).await?;
+        let timestamp = latest_block.timestamp;
+
+        let Some(block_time) = UNIX_EPOCH.checked_add(Duration::from_secs(timestamp)) else {
+            bail!(Error::TimestampOutOfRange { timestamp });
+        };
+
+        let skew = SystemTime::now()
+            .duration_since(block_time)
+            .unwrap_or_else(|error| error.duration());
+
+        if skew > CLOCK_SKEW_WARNING_THRESHOLD {
+            warn!(
+                "timestamp of latest execution block {} differs from local time by {skew:?}; \
+                 check that clocks are synchronized and that the execution client is synced",
+                latest_block.number,
+            );
+        }
+
+        Ok(skew)
+    }
+
+    /// Returns the latest block that the execution client considers safe.
+    ///
+    /// See [`Eth1Api::get_finalized_block`] for caveats.
+    pub async fn get_safe_block(&self) -> Result<Eth1Block> {
+        self.get_block_by_tag(BlockNumber::Safe).await
+    }
+
+    pub async fn get_blocks(
+        &self,
+        block_number_range: RangeInclusive<ExecutionBlockNumber>,
+    ) -> Result<Vec<Eth1Block>> {
+        let mut deposit_data = self
+            .get_deposit_events(block_number_range.clone(), None)
+            .await?;
+        let mut blocks = vec![];
+
+        for block_number in block_number_range {
+            match self.get_block_by_number(block_number).await? {
+                Some(block) => {
+                    let deposit_events = deposit_data.remove(&block_number).unwrap_or_default();
+                    let eth1_block = Eth1Block {
+                        deposit_events: deposit_events.try_into()?,
+                        ..block
+                    };
+                    blocks.push(eth1_block);
+                }
+                None if self.reject_missing_blocks => {
+                    bail!(Error::PrunedBlock {
+                        number: block_number,
+                    })
+                }
+                None => continue,
+            }
+        }
+
+        if self.verify_block_chaining {
+            for (parent, child) in blocks.iter().zip(blocks.iter().skip(1)) {
+                ensure!(
+                    child.parent_hash == parent.hash,
+                    Error::NonContiguousBlocks { at: child.number },
+                );
+            }
+        }
+
+        Ok(blocks)
+    }
+
+    async fn get_block_by_tag(&self, tag: BlockNumber) -> Result<Eth1Block> {
+        let Some(block) = self.get_block(tag.into()).await? else {
+            bail!(Error::BlockTagNotSupported { tag });
+        };
+
+        Ok(block)
+    }
+}
+
+fn block_request(block_id: BlockId) -> Result<(&'static str, Vec<Value>)> {
+    let (method, block) = match block_id {
+        BlockId::Hash(block_hash) => ("eth_getBlockByHash", serde_json::to_value(block_hash)?),
+        BlockId::Number(block_number) => {
+            ("eth_getBlockByNumber", serde_json::to_value(block_number)?)
+        }
+    };
+
+    // The second parameter makes the response contain only transaction hashes.
+    Ok((method, vec![block, Value::Bool(false)]))
+}
+
+fn parse_block(block: Value) -> Result<Option<Eth1Block>> {
+    parse_raw_block(block)?.map(TryInto::try_into).transpose()
+}
+
+fn parse_raw_block(mut block: Value) -> Result<Option<Block<ExecutionTransactionHash>>> {
+    if block.is_null() {
+        return Ok(None);
+    }
+
+    eth1_block::normalize_quantities(&mut block)?;
+
+    Ok(Some(serde_json::from_value(block)?))
+}
+
+// `web3::types::Block` does not have a `withdrawals` field.
+#[derive(Deserialize)]
+struct RawBlockWithdrawals {
+    withdrawals: Option<Vec<WithdrawalV1>>,
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum Error {
+    #[error(
+        "Eth1 RPC endpoint returned no block for tag {tag:?} \
+         (execution clients that have not gone through the Merge do not support it)"
+    )]
+    BlockTagNotSupported { tag: BlockNumber },
+    #[error("different deposit events fetched for block {block_number}")]
+    ConflictingDepositEvents { block_number: ExecutionBlockNumber },
+    #[error(
+        "{deposit_count} deposit events found up to block {block_number} \
+         but deposit contract reports {contract_count} deposits"
+    )]
+    DepositCountMismatch {
+        block_number: ExecutionBlockNumber,
+        deposit_count: u64,
+        contract_count: u64,
+    },
+    #[error("all Eth1 RPC endpoints exhausted")]
+    EndpointsExhausted,
+    #[error(
+        "Eth1 RPC is disabled because this node only syncs \
+         (provide Eth1 RPC endpoints to propose blocks or follow the Merge transition)"
+    )]
+    Eth1Disabled,
+    #[error(
+        "execution client considers payload {block_hash:?} invalid \
+         (validation error: {validation_error:?}); halting as configured"
+    )]
+    FatalInvalidPayload {
+        block_hash: ExecutionBlockHash,
+        validation_error: Option<String>,
+    },
+    #[error("attempted to call Eth1 RPC endpoint with misconfigured parameters")]
+    InvalidParameters,
+    #[error("invalid payload attributes: {0}")]
+    InvalidPayloadAttributes(PayloadAttributesError),
+    #[error("deposit contract returned malformed deposit count: {data:?}")]
+    MalformedDepositCount { data: Bytes },
+    #[error(
+        "Eth1 RPC endpoint returned malformed response to {method}: {error} (response: {})",
+        truncate_body(&.raw.to_string()),
+    )]
+    MalformedResponse {
+        method: String,
+        raw: Value,
+        error: serde_json::Error,
+    },
+    #[error("attempted to call Eth1 RPC endpoint but none were provided")]
+    NoEndpointsProvided,
+    #[error("block {at} returned by Eth1 RPC endpoint is not a child of the block before it")]
+    NonContiguousBlocks { at: ExecutionBlockNumber },
+    #[error(
+        "Eth1 RPC endpoint returned a response that is not valid JSON-RPC 2.0: {}",
+        truncate_body(.message),
+    )]
+    NonJsonResponse { message: String },
+    #[error("failed to get payload from any of the Eth1 RPC endpoints")]
+    NoPayloadsRetrieved,
+    #[error(
+        "payload ID {payload_id:?} is lost because endpoint {endpoint} that issued it \
+         failed to return payload (payload IDs are only known to the endpoint that issued them)"
+    )]
+    PayloadIdLost {
+        endpoint: Url,
+        payload_id: PayloadId,
+    },
+    #[error("pre-Bellatrix phase passed to Eth1Api::forkchoice_updated")]
+    PhasePreBellatrix,
+    #[error("block {number} is missing (the Eth1 RPC endpoint may have pruned it)")]
+    PrunedBlock { number: ExecutionBlockNumber },
+    #[error("requested deposit events for {span} blocks at once (maximum is {max})")]
+    RangeTooLarge { span: u64, max: u64 },
+    #[error("Eth1 RPC endpoint returned block with out-of-range timestamp: {timestamp}")]
+    TimestampOutOfRange { timestamp: UnixSeconds },
+    #[error("{url} is not one of the configured Eth1 RPC endpoints")]
+    UnknownEndpoint { url: Url },
+    #[error("parent of execution payload is not known to Eth1 RPC endpoint: {parent_hash:?}")]
+    UnknownParent { parent_hash: ExecutionBlockHash },
+    #[error("withdrawal index {index} in block does not follow {previous_index}")]
+    WithdrawalIndicesNotConsecutive {
+        previous_index: WithdrawalIndex,
+        index: WithdrawalIndex,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+    use httpmock::{Method, MockServer};
+    use serde_json::json;
+    use ssz::Uint256;
+    use test_case::test_case;
+    use tokio::time;
+    use types::phase0::primitives::H256;
+
+    use crate::{
+        deposit_event::DepositEvent,
+        eth1_api::helpers::{capella_get_payload_response, eth1_api_with_urls, eth1_block_json},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requests_fail_on_sync_only_instance() {
+        let eth1_api = Eth1Api::sync_only(Arc::new(Config::mainnet()), None);
+
+        let error = eth1_api
+            .current_head_number()
+            .await
+            .expect_err("sync-only instance should not make requests");
+
+        assert!(matches!(error.downcast_ref(), Some(Error::Eth1Disabled)));
+        assert_eq!(eth1_api.endpoint_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_receipt() -> Result<()> {
+        let server = MockServer::start();
+        let transaction_hash = H256::repeat_byte(3);
+        let deposit_contract_address = Config::mainnet().deposit_contract_address;
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getTransactionReceipt")
+                .body_contains(format!("{transaction_hash:?}"));
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "transactionHash": transaction_hash,
+                        "transactionIndex": "0x0",
+                        "blockHash": H256::repeat_byte(1),
+                        "blockNumber": "0x64",
+                        "from": ExecutionAddress::repeat_byte(2),
+                        "to": deposit_contract_address,
+                        "cumulativeGasUsed": "0xd4c8",
+                        "gasUsed": "0xd4c8",
+                        "contractAddress": null,
+                        "logs": [{
+                            "address": deposit_contract_address,
+                            "topics": [DepositEvent::TOPIC],
+                            "data": format!("0x{}", "00".repeat(18 * 32)),
+                            "blockHash": H256::repeat_byte(1),
+                            "blockNumber": "0x64",
+                            "transactionHash": transaction_hash,
+                            "transactionIndex": "0x0",
+                            "logIndex": "0x0",
+                            "removed": false,
+                        }],
+                        "logsBloom": format!("0x{}", "00".repeat(256)),
+                        "status": "0x1",
+                        "type": "0x2",
+                        "effectiveGasPrice": "0x3b9aca00",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let receipt = eth1_api
+            .get_transaction_receipt(transaction_hash)
+            .await?
+            .expect("receipt should be found");
+
+        assert_eq!(receipt.transaction_hash, transaction_hash);
+        assert_eq!(receipt.block_number, Some(U64::from(100)));
+        assert_eq!(receipt.status, Some(U64::one()));
+        assert_eq!(receipt.logs.len(), 1);
+        assert_eq!(
+            DepositEvent::try_from(receipt.logs[0].clone())?,
+            DepositEvent::default(),
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_blocks_attaches_deposit_events() -> Result<()> {
+        let server = MockServer::start();
+
+        let deposit_log = |index: u64, amount: u64| {
+            // A `DepositEvent` log takes up 18 EVM words.
+            // The amount is in the 12th one and the index is in the last one.
+            let mut data = [0; 18 * 32];
+            data[11 * 32..11 * 32 + 8].copy_from_slice(&amount.to_le_bytes());
+            data[17 * 32..17 * 32 + 8].copy_from_slice(&index.to_le_bytes());
+
+            json!({
+                "address": Config::mainnet().deposit_contract_address,
+                "topics": [DepositEvent::TOPIC],
+                "data": format!("0x{}", hex::encode(data)),
+                "blockHash": H256::repeat_byte(1),
+                "blockNumber": "0x64",
+                "transactionHash": H256::repeat_byte(3),
+                "transactionIndex": "0x0",
+                "logIndex": "0x0",
+                "removed": false,
+            })
+        };
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [deposit_log(0, 32_000_000_000), deposit_log(1, 1_000_000_000)],
+                })
+                .to_string(),
+            );
+        });
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByNumber");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": eth1_block_json(100, H256::repeat_byte(1), H256::repeat_byte(2)),
+                })
+                .to_string(),
+            );
+        });
+
+        let expected_block = Eth1Block::default()
+            .with_number(100)
+            .with_hash(H256::repeat_byte(1))
+            .with_parent_hash(H256::repeat_byte(2))
+            .with_timestamp(1_674_751_800)
+            .with_total_difficulty(Uint256::from_u64(1))
+            .with_deposit_events(vec![
+                DepositEvent::default().with_amount(32_000_000_000),
+                DepositEvent::default()
+                    .with_index(1)
+                    .with_amount(1_000_000_000),
+            ])?;
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let blocks = eth1_api.get_blocks(100..=100).await?;
+
+        assert_eq!(blocks, [expected_block]);
+
+        Ok(())
+    }
+
+    #[test_case(json!("0x64"), json!("0x63d2af38"); "prefixed hexadecimal strings")]
+    #[test_case(json!("100"), json!("1674751800"); "decimal strings")]
+    #[test_case(json!(100), json!(1_674_751_800_u64); "numbers")]
+    #[tokio::test]
+    async fn test_get_block_accepts_quantity_encoding(
+        number: Value,
+        timestamp: Value,
+    ) -> Result<()> {
+        let server = MockServer::start();
+
+        let mut block = eth1_block_json(100, H256::repeat_byte(1), H256::repeat_byte(2));
+        block["number"] = number;
+        block["timestamp"] = timestamp;
+        block["totalDifficulty"] = json!("1");
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByNumber");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": block,
+                })
+                .to_string(),
+            );
+        });
+
+        let expected_block = Eth1Block::default()
+            .with_number(100)
+            .with_hash(H256::repeat_byte(1))
+            .with_parent_hash(H256::repeat_byte(2))
+            .with_timestamp(1_674_751_800)
+            .with_total_difficulty(Uint256::from_u64(1));
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let block = eth1_api.get_block_by_number(100).await?;
+
+        assert_eq!(block, Some(expected_block));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_block_rejects_invalid_quantity() -> Result<()> {
+        let server = MockServer::start();
+
+        let mut block = eth1_block_json(100, H256::repeat_byte(1), H256::repeat_byte(2));
+        block["number"] = json!(100.5);
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByNumber");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": block,
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        eth1_api
+            .get_block_by_number(100)
+            .await
+            .expect_err("fractional block number should be rejected");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_block_withdrawals() -> Result<()> {
+        let server = MockServer::start();
+        let block_hash = H256::repeat_byte(1);
+
+        let mut block = eth1_block_json(100, block_hash, H256::repeat_byte(2));
+        block["withdrawals"] = capella_get_payload_response("0x0")["result"]["executionPayload"]
+            ["withdrawals"]
+            .clone();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByHash");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": block,
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let withdrawals = eth1_api
+            .get_block_withdrawals(BlockId::Hash(block_hash))
+            .await?
+            .expect("block should contain withdrawals");
+
+        let address = ExecutionAddress::from(hex!("f97e180c050e5ab072211ad2c213eb5aee4df134"));
+
+        assert_eq!(
+            withdrawals,
+            [
+                Withdrawal {
+                    index: 0x18561,
+                    validator_index: 0x7c2e8,
+                    address,
+                    amount: 0x18111,
+                },
+                Withdrawal {
+                    index: 0x18562,
+                    validator_index: 0x7c2e9,
+                    address,
+                    amount: 0x583a6,
+                },
+            ],
+        );
+
+        Ok(())
+    }
+
+    #[test_case("0x18561", "0x18563"; "gap")]
+    #[test_case("0x18561", "0x18561"; "duplicate")]
+    #[test_case("0xffffffffffffffff", "0x0"; "overflow")]
+    #[tokio::test]
+    async fn test_get_block_withdrawals_rejects_non_consecutive_indices(
+        first_index: &str,
+        second_index: &str,
+    ) -> Result<()> {
+        let server = MockServer::start();
+        let block_hash = H256::repeat_byte(1);
+
+        let mut block = eth1_block_json(100, block_hash, H256::repeat_byte(2));
+        block["withdrawals"] = capella_get_payload_response("0x0")["result"]["executionPayload"]
+            ["withdrawals"]
+            .clone();
+        block["withdrawals"][0]["index"] = first_index.into();
+        block["withdrawals"][1]["index"] = second_index.into();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByHash");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": block,
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        // Indices are not checked by default.
+        eth1_api
+            .get_block_withdrawals(BlockId::Hash(block_hash))
+            .await?;
+
+        let error = eth1_api
+            .with_withdrawal_index_verification()
+            .get_block_withdrawals(BlockId::Hash(block_hash))
+            .await
+            .expect_err("withdrawals with non-consecutive indices should be rejected");
+
+        let expected_previous_index =
+            WithdrawalIndex::from_str_radix(first_index.trim_start_matches("0x"), 16)?;
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::WithdrawalIndicesNotConsecutive { previous_index, .. })
+                if *previous_index == expected_previous_index,
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_block_withdrawals_before_capella() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByNumber");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": eth1_block_json(100, H256::repeat_byte(1), H256::repeat_byte(2)),
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let withdrawals = eth1_api
+            .get_block_withdrawals(BlockId::Number(U64::from(100).into()))
+            .await?;
+
+        assert!(withdrawals.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_balance() -> Result<()> {
+        let server = MockServer::start();
+        let address = ExecutionAddress::repeat_byte(0xfe);
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBalance")
+                .body_contains(format!("{address:?}"))
+                .body_contains(r#""0x64""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": "0xde0b6b3a7640000",
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let balance = eth1_api
+            .get_balance(address, BlockNumber::Number(100.into()))
+            .await?;
+
+        assert_eq!(balance, U256::exp10(18));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sync_progress() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_blockNumber");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": "0x3e8",
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let progress = eth1_api.sync_progress(100).await?;
+
+        assert_eq!(progress.head_number, 1000);
+        assert_eq!(progress.blocks_remaining, 900);
+        assert_eq!(progress.estimated_time_remaining, None);
+
+        time::sleep(Duration::from_millis(10)).await;
+
+        let progress = eth1_api.sync_progress(550).await?;
+
+        assert_eq!(progress.blocks_remaining, 450);
+        assert!(progress.estimated_time_remaining.is_some());
+
+        let progress = eth1_api.sync_progress(1000).await?;
+
+        assert_eq!(progress.blocks_remaining, 0);
+        assert_eq!(progress.estimated_time_remaining, Some(Duration::ZERO));
+
+        // A head behind `processed` should not underflow.
+        let progress = eth1_api.sync_progress(1100).await?;
+
+        assert_eq!(progress.blocks_remaining, 0);
+
+        Ok(())
+    }
+
+    #[test_case(H256::repeat_byte(1), true; "chained blocks")]
+    #[test_case(H256::repeat_byte(9), false; "broken chain")]
+    #[tokio::test]
+    async fn test_get_blocks_verifies_chaining(
+        second_parent_hash: ExecutionBlockHash,
+        chained: bool,
+    ) -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [],
+                })
+                .to_string(),
+            );
+        });
+
+        for (number, hash, parent_hash) in [
+            (100, H256::repeat_byte(1), H256::zero()),
+            (101, H256::repeat_byte(2), second_parent_hash),
+        ] {
+            server.mock(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("eth_getBlockByNumber")
+                    .body_contains(format!(r#""0x{number:x}""#));
+
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": eth1_block_json(number, hash, parent_hash),
+                    })
+                    .to_string(),
+                );
+            });
+        }
+
+        let eth1_api =
+            eth1_api_with_urls(vec![server.url("/").parse()?]).with_block_chaining_verification();
+
+        let result = eth1_api.get_blocks(100..=101).await;
+
+        if chained {
+            assert_eq!(result?.len(), 2);
+        } else {
+            let error = result.expect_err("broken chain should be rejected");
+
+            assert!(matches!(
+                error.downcast_ref(),
+                Some(Error::NonContiguousBlocks { at: 101 }),
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test_case(false; "lenient")]
+    #[test_case(true; "strict")]
+    #[tokio::test]
+    async fn test_get_blocks_with_missing_block(strict: bool) -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [],
+                })
+                .to_string(),
+            );
+        });
+
+        for (number, block) in [
+            (
+                100,
+                eth1_block_json(100, H256::repeat_byte(1), H256::zero()),
+            ),
+            (101, Value::Null),
+            (
+                102,
+                eth1_block_json(102, H256::repeat_byte(3), H256::repeat_byte(2)),
+            ),
+        ] {
+            server.mock(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("eth_getBlockByNumber")
+                    .body_contains(format!(r#""0x{number:x}""#));
+
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": block,
+                    })
+                    .to_string(),
+                );
+            });
+        }
+
+        let mut eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        if strict {
+            eth1_api = eth1_api.with_missing_block_rejection();
+        }
+
+        let result = eth1_api.get_blocks(100..=102).await;
+
+        if strict {
+            let error = result.expect_err("missing block should be rejected");
+
+            assert!(matches!(
+                error.downcast_ref(),
+                Some(Error::PrunedBlock { number: 101 }),
+            ));
+        } else {
+            let numbers = result?
+                .into_iter()
+                .map(|block| block.number)
+                .collect::<Vec<_>>();
+
+            assert_eq!(numbers, [100, 102]);
+        }
+
+        Ok(())
+    }
+
+    #[test_case(
+        BlockId::Hash(H256::repeat_byte(1)),
+        "eth_getBlockTransactionCountByHash";
+        "by hash"
+    )]
+    #[test_case(
+        BlockId::Number(BlockNumber::Number(U64::from(100))),
+        "eth_getBlockTransactionCountByNumber";
+        "by number"
+    )]
+    #[tokio::test]
+    async fn test_get_block_transaction_count(block_id: BlockId, method: &str) -> Result<()> {
+        let server = MockServer::start();
+
+        let mock = server.mock(|when, then| {
+            when.method(Method::POST).path("/").body_contains(method);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": "0x2a",
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        assert_eq!(
+            eth1_api.get_block_transaction_count(block_id).await?,
+            Some(42),
+        );
+
+        mock.assert();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_finalized_block() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains(r#""finalized""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": eth1_block_json(100, H256::repeat_byte(1), H256::repeat_byte(2)),
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let block = eth1_api.get_finalized_block().await?;
+
+        assert_eq!(block.number, 100);
+        assert_eq!(block.hash, H256::repeat_byte(1));
+        assert_eq!(block.parent_hash, H256::repeat_byte(2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_pending_block_without_hash() -> Result<()> {
+        let server = MockServer::start();
+
+        let mut pending_block = eth1_block_json(101, H256::zero(), H256::repeat_byte(1));
+        pending_block["hash"] = Value::Null;
+        pending_block["nonce"] = Value::Null;
+
+        if let Value::Object(fields) = &mut pending_block {
+            fields.remove("totalDifficulty");
+        }
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains(r#""pending""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": pending_block,
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let block = eth1_api
+            .get_pending_block()
+            .await?
+            .expect("execution client should return a pending block");
+
+        assert_eq!(block.number, 101);
+        assert_eq!(block.hash, H256::zero());
+        assert_eq!(block.parent_hash, H256::repeat_byte(1));
+        assert_eq!(block.total_difficulty, Uint256::ZERO);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_pending_block_if_not_maintained() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains(r#""pending""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": null,
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        assert!(eth1_api.get_pending_block().await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_clock_skew() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByNumber")
+                .body_contains(r#""latest""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": eth1_block_json(100, H256::repeat_byte(1), H256::repeat_byte(2)),
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let block_time = UNIX_EPOCH + Duration::from_secs(0x63d2_af38);
+        let expected_skew = SystemTime::now().duration_since(block_time)?;
+        let skew = eth1_api.check_clock_skew().await?;
+
+        assert!(skew >= expected_skew);
+        assert!(skew - expected_skew < Duration::from_secs(60));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_clock_skew_with_out_of_range_timestamp() -> Result<()> {
+        let server = MockServer::start();
+
+        let mut block = eth1_block_json(100, H256::repeat_byte(1), H256::repeat_byte(2));
+        block["timestamp"] = json!("0xffffffffffffffff");
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByNumber");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": block,
+                })
+                .to_string(),
+            );
+        });
+
+        let error = eth1_api_with_urls(vec![server.url("/").parse()?])
+            .check_clock_skew()
+            .await
+            .expect_err("timestamp should not fit in SystemTime");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::TimestampOutOfRange {
+                timestamp: UnixSeconds::MAX,
+            }),
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_head_and_finalized() -> Result<()> {
+        let server = MockServer::start();
+
+        let head_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_blockNumber");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": "0x64",
+                })
+                .to_string(),
+            );
+        });
+
+        let finalized_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByNumber")
+                .body_contains(r#""finalized""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": eth1_block_json(90, H256::repeat_byte(1), H256::repeat_byte(2)),
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        assert_eq!(eth1_api.head_and_finalized().await?, (100, 90));
+
+        head_mock.assert_hits(1);
+        finalized_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_safe_block_before_merge() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains(r#""safe""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": null,
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let error = eth1_api
+            .get_safe_block()
+            .await
+            .expect_err("pre-Merge execution client should not return safe block");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::BlockTagNotSupported {
+                tag: BlockNumber::Safe,
+            }),
+        ));
+
+        Ok(())
+    }
+}
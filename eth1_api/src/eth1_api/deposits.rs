@@ -0,0 +1,1096 @@
+use core::ops::RangeInclusive;
+use std::collections::{btree_map::Entry, BTreeMap};
+
+use anyhow::{ensure, Error as AnyhowError, Result};
+use hex_literal::hex;
+use log::warn;
+use types::phase0::primitives::{ExecutionAddress, ExecutionBlockHash, ExecutionBlockNumber};
+use web3::types::{BlockNumber, Bytes, CallRequest, FilterBuilder, Log, TransactionReceipt};
+
+use crate::{
+    deposit_event::DepositEvent,
+    eth1_api::{
+        api::{Error, Eth1Api},
+        transport::is_result_cap_exceeded,
+    },
+};
+
+// The first 4 bytes of `keccak256("get_deposit_count()")`.
+const GET_DEPOSIT_COUNT_SELECTOR: [u8; 4] = hex!("621fd130");
+
+/// Returned by [`Eth1Api::try_get_deposit_events`].
+#[derive(Debug)]
+pub struct PartialDepositEvents {
+    /// Deposit events from the part of the range preceding [`PartialDepositEvents::failure`].
+    pub deposit_events: BTreeMap<ExecutionBlockNumber, Vec<DepositEvent>>,
+    pub failure: Option<DepositRangeFailure>,
+}
+
+impl PartialDepositEvents {
+    pub fn into_result(self) -> Result<BTreeMap<ExecutionBlockNumber, Vec<DepositEvent>>> {
+        match self.failure {
+            Some(failure) => Err(failure.error),
+            None => Ok(self.deposit_events),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DepositRangeFailure {
+    /// The rest of the requested range, starting with the chunk that could not be fetched.
+    pub range: RangeInclusive<ExecutionBlockNumber>,
+    pub error: AnyhowError,
+}
+
+impl Eth1Api {
+    /// Returns the number of the first block containing logs emitted by the deposit contract.
+    ///
+    /// `contract_address` overrides [`Config::deposit_contract_address`] if present.
+    /// The search starts at `from_block` if present, such as the block the contract was deployed in.
+    ///
+    /// [`Config::deposit_contract_address`]: types::config::Config::deposit_contract_address
+    pub async fn get_first_deposit_contract_block_number(
+        &self,
+        contract_address: Option<ExecutionAddress>,
+        from_block: Option<ExecutionBlockNumber>,
+    ) -> Result<Option<ExecutionBlockNumber>> {
+        // `BlockNumber::Earliest` is necessary to get all logs.
+        // `BlockNumber::Latest` is the default (in the JSON RPC, not in `web3`). See:
+        // - <https://github.com/ethereum/wiki/wiki/JSON-RPC/b729c267fd71d9ba92ce6b90023caabc486ca5ae#eth_getlogs>
+        // - <https://github.com/paritytech/wiki/blob/bc0952d26528de087993049fc72e4f6f003e688f/JSONRPC-eth-module.md#eth_newfilter>
+        let from_block = from_block.map_or(BlockNumber::Earliest, |block_number| {
+            BlockNumber::Number(block_number.into())
+        });
+
+        let filter = FilterBuilder::default()
+            .from_block(from_block)
+            .address(vec![self.deposit_contract_address(contract_address)])
+            .limit(1)
+            .build();
+
+        let logs = self
+            .request_with_fallback(|(api, headers)| Ok(api.logs(filter.clone(), headers)))
+            .await?;
+
+        if let Some(log) = logs.first() {
+            if let Some(block_number) = log.block_number {
+                return Ok(Some(block_number.as_u64()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns deposit events in `block_number_range` grouped by block number.
+    ///
+    /// Ranges spanning more blocks than allowed by [`Eth1Api::with_max_deposit_range_span`] are
+    /// rejected unless [`Eth1Api::with_deposit_range_chunking`] has been called.
+    ///
+    /// `contract_address` overrides [`Config::deposit_contract_address`] if present.
+    ///
+    /// [`Config::deposit_contract_address`]: types::config::Config::deposit_contract_address
+    pub async fn get_deposit_events(
+        &self,
+        block_number_range: RangeInclusive<ExecutionBlockNumber>,
+        contract_address: Option<ExecutionAddress>,
+    ) -> Result<BTreeMap<ExecutionBlockNumber, Vec<DepositEvent>>> {
+        let (start, end) = block_number_range.clone().into_inner();
+        let max = self.max_deposit_range_span;
+        let span = end.saturating_add(1).saturating_sub(start);
+
+        if span <= max {
+            return self
+                .get_deposit_events_unchecked(block_number_range, contract_address)
+                .await;
+        }
+
+        ensure!(
+            self.chunk_deposit_ranges && max > 0,
+            Error::RangeTooLarge { span, max },
+        );
+
+        warn!(
+            "requested deposit events for {span} blocks at once \
+             (more than the maximum of {max}); splitting the range into chunks",
+        );
+
+        self.try_get_deposit_events(block_number_range, contract_address)
+            .await
+            .into_result()
+    }
+
+    /// Like [`Eth1Api::get_deposit_events`], but always splits `block_number_range` into chunks
+    /// and keeps the events fetched before a chunk fails.
+    ///
+    /// Callers can persist the events they got and retry only [`DepositRangeFailure::range`].
+    pub async fn try_get_deposit_events(
+        &self,
+        block_number_range: RangeInclusive<ExecutionBlockNumber>,
+        contract_address: Option<ExecutionAddress>,
+    ) -> PartialDepositEvents {
+        let (start, end) = block_number_range.into_inner();
+        let max = self.max_deposit_range_span.max(1);
+
+        let mut deposit_events = BTreeMap::new();
+        let mut chunk_start = start;
+
+        while chunk_start <= end {
+            let chunk_end = end.min(chunk_start.saturating_add(max - 1));
+
+            match self
+                .get_deposit_events_unchecked(chunk_start..=chunk_end, contract_address)
+                .await
+            {
+                Ok(chunk) => deposit_events.extend(chunk),
+                Err(error) => {
+                    return PartialDepositEvents {
+                        deposit_events,
+                        failure: Some(DepositRangeFailure {
+                            range: chunk_start..=end,
+                            error,
+                        }),
+                    }
+                }
+            }
+
+            let Some(next_chunk_start) = chunk_end.checked_add(1) else {
+                break;
+            };
+
+            chunk_start = next_chunk_start;
+        }
+
+        PartialDepositEvents {
+            deposit_events,
+            failure: None,
+        }
+    }
+
+    async fn get_deposit_events_unchecked(
+        &self,
+        block_number_range: RangeInclusive<ExecutionBlockNumber>,
+        contract_address: Option<ExecutionAddress>,
+    ) -> Result<BTreeMap<ExecutionBlockNumber, Vec<DepositEvent>>> {
+        // Sepolia uses a custom contract that emits events other than `DepositEvent`. See:
+        // - <https://github.com/ethereum/pm/issues/526>
+        // - <https://github.com/protolambda/testnet-dep-contract/blob/8df70175dca186b74197ec830450c4b988861746/deposit_contract.sol>
+        // - <https://notes.ethereum.org/zvkfSmYnT0-uxwwEegbCqg>
+        // - <https://sepolia.etherscan.io/address/0x7f02C3E3c98b133055B8B348B2Ac625669Ed295D#events>
+        // - <https://sepolia.etherscan.io/token/0x7f02C3E3c98b133055B8B348B2Ac625669Ed295D>
+        let contract_address = self.deposit_contract_address(contract_address);
+
+        let mut deposit_events = BTreeMap::<_, Vec<_>>::new();
+
+        for log in self
+            .get_deposit_logs(block_number_range.clone(), contract_address)
+            .await?
+        {
+            let block_number = match log.block_number {
+                Some(block_number) => block_number.as_u64(),
+                None => continue,
+            };
+
+            let deposit_event = DepositEvent::try_from(log)?;
+
+            deposit_events
+                .entry(block_number)
+                .or_default()
+                .push(deposit_event);
+        }
+
+        if self.verify_deposits_with_receipts {
+            self.verify_deposit_events_with_receipts(
+                block_number_range,
+                contract_address,
+                &mut deposit_events,
+            )
+            .await?;
+        }
+
+        Ok(deposit_events)
+    }
+
+    // Some execution clients and RPC providers limit the number of logs in a single response.
+    // Ranges that exceed the limit are bisected until every part fits.
+    async fn get_deposit_logs(
+        &self,
+        block_number_range: RangeInclusive<ExecutionBlockNumber>,
+        contract_address: ExecutionAddress,
+    ) -> Result<Vec<Log>> {
+        let mut logs = vec![];
+
+        // Ranges are popped from the end, so the earliest one has to be pushed last.
+        let mut pending_ranges = vec![block_number_range];
+
+        while let Some(range) = pending_ranges.pop() {
+            let (start, end) = range.into_inner();
+
+            let filter = FilterBuilder::default()
+                .from_block(start.into())
+                .to_block(end.into())
+                .address(vec![contract_address])
+                .topics(Some(vec![DepositEvent::TOPIC]), None, None, None)
+                .build();
+
+            match self
+                .request_with_fallback(|(api, headers)| Ok(api.logs(filter.clone(), headers)))
+                .await
+            {
+                Ok(range_logs) => logs.extend(range_logs),
+                Err(error) if start < end && is_result_cap_exceeded(&error) => {
+                    let middle = start + (end - start) / 2;
+
+                    warn!(
+                        "Eth1 RPC endpoint returned too many logs for blocks {start}..={end}: \
+                         {error}; splitting the range in half",
+                    );
+
+                    pending_ranges.push(middle + 1..=end);
+                    pending_ranges.push(start..=middle);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(logs)
+    }
+
+    async fn verify_deposit_events_with_receipts(
+        &self,
+        block_number_range: RangeInclusive<ExecutionBlockNumber>,
+        contract_address: ExecutionAddress,
+        deposit_events: &mut BTreeMap<ExecutionBlockNumber, Vec<DepositEvent>>,
+    ) -> Result<()> {
+        for block_number in block_number_range {
+            // The endpoint may not have the block if the chain reorganized in the meantime.
+            // Blocks from the old chain are detected by other means.
+            let Some(receipts) = self.get_block_receipts(block_number).await? else {
+                continue;
+            };
+
+            let receipt_events = self
+                .deposit_events_from_receipts(&receipts, Some(contract_address))?
+                .remove(&block_number)
+                .unwrap_or_default();
+
+            let log_count = deposit_events.get(&block_number).map_or(0, Vec::len);
+            let receipt_count = receipt_events.len();
+
+            if log_count == receipt_count {
+                continue;
+            }
+
+            warn!(
+                "eth_getLogs returned {log_count} deposit events for block {block_number} \
+                 but its receipts contain {receipt_count}; using deposit events from receipts",
+            );
+
+            if receipt_events.is_empty() {
+                deposit_events.remove(&block_number);
+            } else {
+                deposit_events.insert(block_number, receipt_events);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like [`Eth1Api::get_deposit_events`], but only keeps deposits whose pubkey starts with
+    /// one of `pubkey_prefixes`.
+    ///
+    /// Logs cannot be filtered by pubkey on the execution client side,
+    /// so this saves downstream processing rather than bandwidth.
+    pub async fn get_deposit_events_with_pubkey_prefixes(
+        &self,
+        block_number_range: RangeInclusive<ExecutionBlockNumber>,
+        pubkey_prefixes: &[&[u8]],
+    ) -> Result<BTreeMap<ExecutionBlockNumber, Vec<DepositEvent>>> {
+        let mut deposit_events = self.get_deposit_events(block_number_range, None).await?;
+
+        deposit_events.retain(|_, events| {
+            events.retain(|event| {
+                let pubkey = event.data.pubkey.as_bytes();
+                pubkey_prefixes
+                    .iter()
+                    .any(|prefix| pubkey.starts_with(prefix))
+            });
+
+            !events.is_empty()
+        });
+
+        Ok(deposit_events)
+    }
+
+    /// Extracts deposit events from the logs in `receipts` and groups them by block number.
+    ///
+    /// Callers that already fetch receipts (e.g. with [`Eth1Api::get_block_receipts`]) can use
+    /// this instead of making separate `eth_getLogs` requests.
+    pub fn deposit_events_from_receipts(
+        &self,
+        receipts: &[TransactionReceipt],
+        contract_address: Option<ExecutionAddress>,
+    ) -> Result<BTreeMap<ExecutionBlockNumber, Vec<DepositEvent>>> {
+        let contract_address = self.deposit_contract_address(contract_address);
+        let mut deposit_events = BTreeMap::<_, Vec<_>>::new();
+
+        for log in receipts.iter().flat_map(|receipt| &receipt.logs) {
+            if log.address != contract_address || log.topics != [DepositEvent::TOPIC] {
+                continue;
+            }
+
+            let block_number = match log.block_number {
+                Some(block_number) => block_number.as_u64(),
+                None => continue,
+            };
+
+            let deposit_event = DepositEvent::try_from(log.clone())?;
+
+            deposit_events
+                .entry(block_number)
+                .or_default()
+                .push(deposit_event);
+        }
+
+        Ok(deposit_events)
+    }
+
+    /// Merges deposit events fetched for possibly overlapping ranges (e.g. concurrently).
+    ///
+    /// Fails if the same block has different events in different maps.
+    pub fn merge_deposit_events(
+        maps: Vec<BTreeMap<ExecutionBlockNumber, Vec<DepositEvent>>>,
+    ) -> Result<BTreeMap<ExecutionBlockNumber, Vec<DepositEvent>>> {
+        let mut merged = BTreeMap::new();
+
+        for (block_number, deposit_events) in maps.into_iter().flatten() {
+            match merged.entry(block_number) {
+                Entry::Vacant(vacant) => {
+                    vacant.insert(deposit_events);
+                }
+                Entry::Occupied(occupied) => ensure!(
+                    *occupied.get() == deposit_events,
+                    Error::ConflictingDepositEvents { block_number },
+                ),
+            }
+        }
+
+        Ok(merged)
+    }
+
+    /// Calls `get_deposit_count` on the deposit contract as of `block`.
+    ///
+    /// This is much cheaper than counting deposit events.
+    /// See [`Eth1Api::verify_deposit_count`] for checking a count derived from them.
+    pub async fn get_deposit_count(&self, block: BlockNumber) -> Result<u64> {
+        let call_request = CallRequest {
+            to: Some(self.config.deposit_contract_address),
+            data: Some(Bytes(GET_DEPOSIT_COUNT_SELECTOR.to_vec())),
+            ..CallRequest::default()
+        };
+
+        let data = self
+            .request_with_fallback(|(api, headers)| {
+                Ok(api.call(call_request.clone(), Some(block.into()), headers))
+            })
+            .await?;
+
+        // The deposit contract returns the count as `bytes` containing a little-endian `uint64`.
+        // The ABI encoding consists of an offset, a length, and the padded value itself.
+        ensure!(data.0.len() == 96, Error::MalformedDepositCount { data });
+
+        Ok(u64::from_le_bytes(data.0[64..72].try_into()?))
+    }
+
+    /// Checks that `deposit_count` derived from deposit events emitted up to and including
+    /// `block_number` matches the count reported by the deposit contract as of that block.
+    ///
+    /// A mismatch means that some deposit events were missed or fetched twice.
+    pub async fn verify_deposit_count(
+        &self,
+        block_number: ExecutionBlockNumber,
+        deposit_count: u64,
+    ) -> Result<()> {
+        let contract_count = self
+            .get_deposit_count(BlockNumber::Number(block_number.into()))
+            .await?;
+
+        ensure!(
+            contract_count == deposit_count,
+            Error::DepositCountMismatch {
+                block_number,
+                deposit_count,
+                contract_count,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Fetches deposit events emitted in the block with hash `block_hash`.
+    ///
+    /// Unlike [`Eth1Api::get_deposit_events`], this cannot return logs from a different block
+    /// if the chain reorganizes while the request is in flight.
+    pub async fn get_deposit_events_for_block_hash(
+        &self,
+        block_hash: ExecutionBlockHash,
+    ) -> Result<Vec<DepositEvent>> {
+        let filter = FilterBuilder::default()
+            .block_hash(block_hash)
+            .address(vec![self.config.deposit_contract_address])
+            .topics(Some(vec![DepositEvent::TOPIC]), None, None, None)
+            .build();
+
+        self.request_with_fallback(|(api, headers)| Ok(api.logs(filter.clone(), headers)))
+            .await?
+            .into_iter()
+            .map(DepositEvent::try_from)
+            .collect()
+    }
+
+    fn deposit_contract_address(
+        &self,
+        contract_address: Option<ExecutionAddress>,
+    ) -> ExecutionAddress {
+        contract_address.unwrap_or(self.config.deposit_contract_address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method, MockServer};
+    use serde_json::json;
+    use test_case::test_case;
+    use types::{config::Config, phase0::primitives::H256};
+
+    use crate::eth1_api::helpers::{deposit_log_json, eth1_api_with_urls, receipt_json};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_deposit_events_for_block_hash() -> Result<()> {
+        let server = MockServer::start();
+        let block_hash = H256::repeat_byte(1);
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs")
+                .body_contains(format!(r#""blockHash":"{block_hash:?}""#));
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [{
+                        "address": Config::mainnet().deposit_contract_address,
+                        "topics": [DepositEvent::TOPIC],
+                        // A `DepositEvent` log with all fields zeroed out takes up 18 EVM words.
+                        "data": format!("0x{}", "00".repeat(18 * 32)),
+                        "blockHash": block_hash,
+                        "blockNumber": "0x64",
+                        "transactionHash": H256::repeat_byte(3),
+                        "transactionIndex": "0x0",
+                        "logIndex": "0x0",
+                        "removed": false,
+                    }],
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let deposit_events = eth1_api
+            .get_deposit_events_for_block_hash(block_hash)
+            .await?;
+
+        assert_eq!(deposit_events, [DepositEvent::default()]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deposit_events_with_pubkey_prefixes() -> Result<()> {
+        let server = MockServer::start();
+
+        let deposit_log = |block_number: &str, pubkey_prefix: [u8; 2]| {
+            // A `DepositEvent` log takes up 18 EVM words. The pubkey starts in the 7th one.
+            let mut data = [0; 18 * 32];
+            data[6 * 32..6 * 32 + 2].copy_from_slice(&pubkey_prefix);
+
+            json!({
+                "address": Config::mainnet().deposit_contract_address,
+                "topics": [DepositEvent::TOPIC],
+                "data": format!("0x{}", hex::encode(data)),
+                "blockHash": H256::repeat_byte(1),
+                "blockNumber": block_number,
+                "transactionHash": H256::repeat_byte(3),
+                "transactionIndex": "0x0",
+                "logIndex": "0x0",
+                "removed": false,
+            })
+        };
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [
+                        deposit_log("0x64", [0xaa, 0x01]),
+                        deposit_log("0x64", [0xbb, 0x02]),
+                        deposit_log("0x65", [0xbb, 0x03]),
+                        deposit_log("0x66", [0xcc, 0x04]),
+                    ],
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let deposit_events = eth1_api
+            .get_deposit_events_with_pubkey_prefixes(100..=102, &[&[0xaa], &[0xcc, 0x04]])
+            .await?;
+
+        let pubkey_prefixes = deposit_events
+            .iter()
+            .map(|(block_number, events)| {
+                let prefixes = events
+                    .iter()
+                    .map(|event| event.data.pubkey.as_bytes()[..2].to_vec())
+                    .collect::<Vec<_>>();
+
+                (*block_number, prefixes)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            pubkey_prefixes,
+            [(100, vec![vec![0xaa, 0x01]]), (102, vec![vec![0xcc, 0x04]])],
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_deposit_events_with_consistent_overlap() -> Result<()> {
+        let event = |index| DepositEvent::default().with_index(index);
+
+        let first = BTreeMap::from([(1, vec![event(0)]), (2, vec![event(1), event(2)])]);
+        let second = BTreeMap::from([(2, vec![event(1), event(2)]), (5, vec![event(3)])]);
+
+        let merged = Eth1Api::merge_deposit_events(vec![second, first])?;
+
+        assert_eq!(
+            merged,
+            BTreeMap::from([
+                (1, vec![event(0)]),
+                (2, vec![event(1), event(2)]),
+                (5, vec![event(3)]),
+            ]),
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_deposit_events_detects_conflict() {
+        let event = |index| DepositEvent::default().with_index(index);
+
+        let first = BTreeMap::from([(1, vec![event(0)]), (2, vec![event(1)])]);
+        let second = BTreeMap::from([(2, vec![event(1), event(2)])]);
+
+        let error = Eth1Api::merge_deposit_events(vec![first, second])
+            .expect_err("maps with different events for block 2 should not be merged");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::ConflictingDepositEvents { block_number: 2 }),
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_try_get_deposit_events_keeps_chunks_before_failure() -> Result<()> {
+        let server = MockServer::start();
+
+        let deposit_log = |block_number: u64, index: u64| {
+            let mut data = [0; 18 * 32];
+            data[17 * 32..17 * 32 + 8].copy_from_slice(&index.to_le_bytes());
+
+            json!({
+                "address": Config::mainnet().deposit_contract_address,
+                "topics": [DepositEvent::TOPIC],
+                "data": format!("0x{}", hex::encode(data)),
+                "blockHash": H256::repeat_byte(1),
+                "blockNumber": format!("0x{block_number:x}"),
+                "transactionHash": H256::repeat_byte(3),
+                "transactionIndex": "0x0",
+                "logIndex": "0x0",
+                "removed": false,
+            })
+        };
+
+        for (from_block, logs) in [
+            (0, vec![deposit_log(5, 0)]),
+            (10, vec![deposit_log(15, 1)]),
+            (30, vec![]),
+            (40, vec![]),
+        ] {
+            server.mock(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("eth_getLogs")
+                    .body_contains(format!(r#""fromBlock":"0x{from_block:x}""#));
+
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": logs,
+                    })
+                    .to_string(),
+                );
+            });
+        }
+
+        let failing_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs")
+                .body_contains(r#""fromBlock":"0x14""#);
+
+            then.status(500);
+        });
+
+        let eth1_api =
+            eth1_api_with_urls(vec![server.url("/").parse()?]).with_max_deposit_range_span(10);
+
+        let partial = eth1_api.try_get_deposit_events(0..=49, None).await;
+
+        assert_eq!(
+            partial.deposit_events,
+            BTreeMap::from([
+                (5, vec![DepositEvent::default()]),
+                (15, vec![DepositEvent::default().with_index(1)]),
+            ]),
+        );
+
+        let failure = partial
+            .failure
+            .expect("chunk starting at block 20 should fail");
+
+        assert_eq!(failure.range, 20..=49);
+        assert!(matches!(
+            failure.error.downcast_ref(),
+            Some(Error::EndpointsExhausted),
+        ));
+
+        failing_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deposit_events_with_custom_contract_address() -> Result<()> {
+        let server = MockServer::start();
+        let contract_address = ExecutionAddress::repeat_byte(0x42);
+
+        let logs_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs")
+                .body_contains(format!("{contract_address:?}"));
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [],
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        assert_ne!(eth1_api.config.deposit_contract_address, contract_address);
+
+        eth1_api
+            .get_deposit_events(0..=9, Some(contract_address))
+            .await?;
+
+        logs_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_first_deposit_contract_block_number_from_block() -> Result<()> {
+        let server = MockServer::start();
+
+        let logs_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs")
+                .body_contains(r#""fromBlock":"0x3e8""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [deposit_log_json("0x3ed", 0)],
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        assert_eq!(
+            eth1_api
+                .get_first_deposit_contract_block_number(None, Some(1000))
+                .await?,
+            Some(1005),
+        );
+
+        logs_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deposit_events_bisects_ranges_with_too_many_logs() -> Result<()> {
+        let server = MockServer::start();
+
+        let full_range_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs")
+                .body_contains(r#""fromBlock":"0x0""#)
+                .body_contains(r#""toBlock":"0x9""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "error": {
+                        "code": -32005,
+                        "message": "query returned more than 10000 results",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let half_range_mocks = [("0x0", "0x4", "0x2", 0), ("0x5", "0x9", "0x7", 1)].map(
+            |(from_block, to_block, block_number, index)| {
+                server.mock(|when, then| {
+                    when.method(Method::POST)
+                        .path("/")
+                        .body_contains("eth_getLogs")
+                        .body_contains(format!(r#""fromBlock":"{from_block}""#))
+                        .body_contains(format!(r#""toBlock":"{to_block}""#));
+
+                    then.status(200).body(
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": 0,
+                            "result": [deposit_log_json(block_number, index)],
+                        })
+                        .to_string(),
+                    );
+                })
+            },
+        );
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let deposit_events = eth1_api.get_deposit_events(0..=9, None).await?;
+
+        full_range_mock.assert_hits(1);
+
+        for mock in half_range_mocks {
+            mock.assert_hits(1);
+        }
+
+        let indices = deposit_events
+            .iter()
+            .flat_map(|(block_number, events)| {
+                events.iter().map(|event| (*block_number, event.index))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(indices, [(2, 0), (7, 1)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deposit_events_rejects_range_that_is_too_large() -> Result<()> {
+        let server = MockServer::start();
+
+        let logs_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [],
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api =
+            eth1_api_with_urls(vec![server.url("/").parse()?]).with_max_deposit_range_span(10);
+
+        eth1_api.get_deposit_events(0..=9, None).await?;
+
+        let error = eth1_api
+            .get_deposit_events(0..=10, None)
+            .await
+            .expect_err("range spanning 11 blocks should be rejected");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::RangeTooLarge { span: 11, max: 10 }),
+        ));
+
+        logs_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deposit_events_from_receipts() -> Result<()> {
+        let server = MockServer::start();
+
+        let mut other_log = deposit_log_json("0x64", 9);
+        other_log["address"] = json!(ExecutionAddress::repeat_byte(4));
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockReceipts")
+                .body_contains(r#"["0x64"]"#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [
+                        receipt_json("0x64", vec![deposit_log_json("0x64", 0)]),
+                        receipt_json("0x64", vec![other_log]),
+                        receipt_json("0x64", vec![deposit_log_json("0x64", 1)]),
+                    ],
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let receipts = eth1_api
+            .get_block_receipts(100)
+            .await?
+            .expect("block should be known");
+
+        let deposit_indices = eth1_api
+            .deposit_events_from_receipts(&receipts, None)?
+            .into_iter()
+            .map(|(block_number, events)| {
+                let indices = events.iter().map(|event| event.index).collect::<Vec<_>>();
+                (block_number, indices)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(receipts.len(), 3);
+        assert_eq!(deposit_indices, [(100, vec![0, 1])]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deposit_events_recovers_deposits_missing_from_logs() -> Result<()> {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [deposit_log_json("0x64", 0)],
+                })
+                .to_string(),
+            );
+        });
+
+        for (block_number, logs) in [
+            ("0x64", vec![deposit_log_json("0x64", 0)]),
+            ("0x65", vec![deposit_log_json("0x65", 1)]),
+        ] {
+            server.mock(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("eth_getBlockReceipts")
+                    .body_contains(format!(r#"["{block_number}"]"#));
+
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": [receipt_json(block_number, logs)],
+                    })
+                    .to_string(),
+                );
+            });
+        }
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let unverified_indices = eth1_api
+            .get_deposit_events(100..=101, None)
+            .await?
+            .into_values()
+            .flatten()
+            .map(|event| event.index)
+            .collect::<Vec<_>>();
+
+        assert_eq!(unverified_indices, [0]);
+
+        let verified_indices = eth1_api
+            .with_deposit_receipt_verification()
+            .get_deposit_events(100..=101, None)
+            .await?
+            .into_values()
+            .flatten()
+            .map(|event| event.index)
+            .collect::<Vec<_>>();
+
+        assert_eq!(verified_indices, [0, 1]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deposit_events_splits_range_that_is_too_large() -> Result<()> {
+        let server = MockServer::start();
+
+        let logs_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getLogs");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": [],
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?])
+            .with_max_deposit_range_span(10)
+            .with_deposit_range_chunking();
+
+        eth1_api.get_deposit_events(0..=24, None).await?;
+
+        logs_mock.assert_hits(3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deposit_count() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_call")
+                .body_contains("0x621fd130");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": concat!(
+                        "0x",
+                        "0000000000000000000000000000000000000000000000000000000000000020",
+                        "0000000000000000000000000000000000000000000000000000000000000008",
+                        "3930000000000000000000000000000000000000000000000000000000000000",
+                    ),
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        assert_eq!(
+            eth1_api.get_deposit_count(BlockNumber::Latest).await?,
+            12_345
+        );
+
+        Ok(())
+    }
+
+    #[test_case(12_345, true; "matching")]
+    #[test_case(12_344, false; "missing deposit")]
+    #[tokio::test]
+    async fn test_verify_deposit_count(deposit_count: u64, matching: bool) -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_call")
+                .body_contains("0x621fd130")
+                .body_contains(r#""0x64""#);
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": concat!(
+                        "0x",
+                        "0000000000000000000000000000000000000000000000000000000000000020",
+                        "0000000000000000000000000000000000000000000000000000000000000008",
+                        "3930000000000000000000000000000000000000000000000000000000000000",
+                    ),
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let result = eth1_api.verify_deposit_count(100, deposit_count).await;
+
+        if matching {
+            result?;
+        } else {
+            let error = result.expect_err("deposit counts should not match");
+
+            assert!(matches!(
+                error.downcast_ref(),
+                Some(Error::DepositCountMismatch {
+                    block_number: 100,
+                    deposit_count: 12_344,
+                    contract_count: 12_345,
+                }),
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,2587 @@
+#![allow(clippy::module_name_repetitions)]
+
+use core::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::{bail, ensure, Error as AnyhowError, Result};
+use either::Either;
+use enum_iterator::Sequence as _;
+use ethereum_types::H64;
+use execution_engine::{
+    EngineGetPayloadV1Response, EngineGetPayloadV2BellatrixResponse, EngineGetPayloadV2Response,
+    EngineGetPayloadV3Response, ExecutionPayloadV1, ExecutionPayloadV2, ExecutionPayloadV3,
+    ForkChoiceStateV1, ForkChoiceUpdatedResponse, PayloadAttributes, PayloadId, PayloadStatusV1,
+    TransitionConfigurationV1,
+};
+use futures::future;
+use helper_functions::misc;
+use log::{error, warn};
+use reqwest::Url;
+use serde::Deserialize;
+#[cfg(feature = "optimism")]
+use serde::Serialize;
+use serde_json::Value;
+use static_assertions::const_assert_eq;
+use std_ext::CopyExt;
+use thiserror::Error;
+use types::{
+    capella::primitives::WithdrawalIndex,
+    combined::{ExecutionPayload, ExecutionPayloadParams},
+    nonstandard::{Phase, WithBlobsAndMev},
+    phase0::primitives::{ExecutionBlockHash, Slot, UnixSeconds},
+    preset::Preset,
+};
+use web3::types::{Bytes, U64};
+
+use crate::eth1_api::{
+    api::{Error, Eth1Api},
+    transport::{is_method_not_found, is_transport_error},
+};
+
+const ENGINE_FORKCHOICE_UPDATED_TIMEOUT: Duration = Duration::from_secs(8);
+pub(crate) const ENGINE_GET_PAYLOAD_TIMEOUT: Duration = Duration::from_secs(1);
+const ENGINE_NEW_PAYLOAD_TIMEOUT: Duration = Duration::from_secs(8);
+const ENGINE_EXCHANGE_TRANSITION_CONFIGURATION_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Only the most recent payload IDs are worth remembering.
+// Payloads are requested shortly after `engine_forkchoiceUpdated` or not at all.
+const MAX_PINNED_PAYLOAD_IDS: usize = 16;
+
+// Execution clients busy building a payload may miss the first request for it.
+// Requests for pinned payloads cannot fall back to other endpoints, so they are retried instead.
+const MAX_PINNED_GET_PAYLOAD_ATTEMPTS: usize = 2;
+
+/// Extra payload attributes accepted by execution clients of the [OP Stack].
+///
+/// They are only sent when the `optimism` feature is enabled
+/// and [`Eth1Api::with_optimism_payload_attributes`] has been called.
+///
+/// [OP Stack]: https://specs.optimism.io/protocol/exec-engine.html#extended-payloadattributesv3
+#[cfg(feature = "optimism")]
+#[derive(Clone, Default, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimismPayloadAttributes {
+    pub transactions: Vec<Bytes>,
+    pub no_tx_pool: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<U64>,
+}
+
+/// Receives metadata about every Engine API call made by [`Eth1Api`].
+///
+/// Meant for building audit trails. Parameters are not passed to observers because they may
+/// contain sensitive data. Observers are called synchronously and should not block.
+pub trait EngineCallObserver: Send + Sync {
+    fn observe(&self, call: &EngineCall);
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct EngineCall<'method> {
+    pub method: &'method str,
+    /// Hash of the JSON-encoded parameters.
+    /// Only suitable for telling calls apart, not for any kind of verification.
+    pub params_hash: u64,
+    pub succeeded: bool,
+    pub elapsed: Duration,
+    pub endpoints_tried: usize,
+}
+
+/// Returned by [`Eth1Api::recent_engine_calls`].
+#[derive(Clone, Debug)]
+pub struct EngineCallRecord {
+    pub method: String,
+    /// `None` if the call succeeded.
+    pub error: Option<String>,
+    pub elapsed: Duration,
+    /// When the call was made.
+    pub timestamp: SystemTime,
+}
+
+/// Returned by [`Eth1Api::new_payload_cross_check`].
+#[derive(Clone, Debug)]
+pub struct NewPayloadCrossCheck {
+    pub first: PayloadStatusV1,
+    pub second: PayloadStatusV1,
+    /// Whether one execution client considered the payload valid and the other invalid.
+    /// Other combinations of statuses (e.g. `VALID` and `SYNCING`) are not divergences.
+    pub diverged: bool,
+}
+
+impl Eth1Api {
+    /// Returns the most recent Engine API calls from oldest to newest.
+    ///
+    /// Always empty unless [`Eth1Api::with_recent_engine_calls`] has been called.
+    /// Meant for debugging failures after the fact without logging every call.
+    pub async fn recent_engine_calls(&self) -> Vec<EngineCallRecord> {
+        self.recent_engine_calls
+            .lock()
+            .await
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Calls [`engine_newPayloadV1`] or [`engine_newPayloadV2`] or [`engine_newPayloadV3`] depending on `payload`.
+    ///
+    /// Later versions of `engine_newPayload` accept parameters of all prior versions,
+    /// but using the earlier versions allows the application to work with old execution clients.
+    ///
+    /// [`engine_newPayloadV1`]: https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/paris.md#engine_newpayloadv1
+    /// [`engine_newPayloadV2`]: https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/shanghai.md#engine_newpayloadv2
+    /// [`engine_newPayloadV3`]: https://github.com/ethereum/execution-apis/blob/a0d03086564ab1838b462befbc083f873dcf0c0f/src/engine/cancun.md#engine_newpayloadv3
+    pub async fn new_payload<P: Preset>(
+        &self,
+        payload: ExecutionPayload<P>,
+        params: Option<ExecutionPayloadParams>,
+    ) -> Result<PayloadStatusV1> {
+        self.new_payload_with_timeout(payload, params, None).await
+    }
+
+    /// Like [`Eth1Api::new_payload`], but `timeout` overrides the default timeout for this call
+    /// if present. This includes timeouts set with [`Eth1Api::with_new_payload_timeout`].
+    pub async fn new_payload_with_timeout<P: Preset>(
+        &self,
+        payload: ExecutionPayload<P>,
+        params: Option<ExecutionPayloadParams>,
+        timeout: Option<Duration>,
+    ) -> Result<PayloadStatusV1> {
+        let timeout = timeout.unwrap_or_else(|| self.new_payload_timeout(payload.phase()));
+        let block_hash = payload.block_hash();
+        let payload_status = self
+            .send_new_payload(None, payload, params, timeout)
+            .await?;
+
+        // A rising share of `INVALID` statuses is an early sign of problems with blocks or
+        // the execution client.
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics
+                .eth1_api_payload_statuses
+                .with_label_values(&[payload_status.status.as_ref()])
+                .inc();
+        }
+
+        if self.fatal_invalid_payloads && payload_status.status.is_invalid() {
+            bail!(Error::FatalInvalidPayload {
+                block_hash,
+                validation_error: payload_status.validation_error,
+            });
+        }
+
+        Ok(payload_status)
+    }
+
+    /// Submits `payload` to the execution clients at `first` and `second` and compares their
+    /// verdicts.
+    ///
+    /// Meant for operators running redundant execution clients of different implementations.
+    /// A payload that one of them considers valid and the other invalid indicates a bug in one of
+    /// the clients and a potential consensus split.
+    pub async fn new_payload_cross_check<P: Preset>(
+        &self,
+        payload: ExecutionPayload<P>,
+        params: Option<ExecutionPayloadParams>,
+        first: &Url,
+        second: &Url,
+    ) -> Result<NewPayloadCrossCheck> {
+        for url in [first, second] {
+            ensure!(
+                self.original.contains(url),
+                Error::UnknownEndpoint { url: url.clone() },
+            );
+        }
+
+        let timeout = self.new_payload_timeout(payload.phase());
+        let block_hash = payload.block_hash();
+
+        let (first_status, second_status) = future::try_join(
+            self.send_new_payload(Some(first), payload.clone(), params.clone(), timeout),
+            self.send_new_payload(Some(second), payload, params, timeout),
+        )
+        .await?;
+
+        let diverged = (first_status.status.is_valid() && second_status.status.is_invalid())
+            || (first_status.status.is_invalid() && second_status.status.is_valid());
+
+        if diverged {
+            error!(
+                "execution clients disagree on validity of payload {block_hash:?} \
+                 ({first}: {first_status:?}, {second}: {second_status:?}); \
+                 one of them may have a consensus bug",
+            );
+
+            if let Some(metrics) = self.metrics.as_ref() {
+                metrics.eth1_api_new_payload_divergences_count.inc();
+            }
+        }
+
+        Ok(NewPayloadCrossCheck {
+            first: first_status,
+            second: second_status,
+            diverged,
+        })
+    }
+
+    // Sends the request to `endpoint` without falling back to other endpoints if it is present.
+    async fn send_new_payload<P: Preset>(
+        &self,
+        endpoint: Option<&Url>,
+        payload: ExecutionPayload<P>,
+        params: Option<ExecutionPayloadParams>,
+        timeout: Duration,
+    ) -> Result<PayloadStatusV1> {
+        match (payload, params) {
+            (ExecutionPayload::Bellatrix(payload), None) => {
+                let payload_v1 = ExecutionPayloadV1::from(payload);
+                let params = vec![serde_json::to_value(payload_v1)?];
+                let result = self
+                    .execute_on(endpoint, "engine_newPayloadV1", params.clone(), timeout)
+                    .await;
+
+                // `engine_newPayloadV2` accepts `ExecutionPayloadV1`.
+                match result {
+                    Err(error) if is_method_not_found(&error) => {
+                        warn!(
+                            "execution client does not support engine_newPayloadV1; \
+                             retrying with engine_newPayloadV2",
+                        );
+
+                        self.execute_on(endpoint, "engine_newPayloadV2", params, timeout)
+                            .await
+                    }
+                    result => result,
+                }
+            }
+            (ExecutionPayload::Capella(payload), None) => {
+                let payload_v2 = ExecutionPayloadV2::from(payload);
+                let params = vec![serde_json::to_value(payload_v2)?];
+                self.execute_on(endpoint, "engine_newPayloadV2", params, timeout)
+                    .await
+            }
+            (
+                ExecutionPayload::Deneb(payload),
+                Some(ExecutionPayloadParams::Deneb {
+                    versioned_hashes,
+                    parent_beacon_block_root,
+                }),
+            ) => {
+                let payload_v3 = ExecutionPayloadV3::from(payload);
+                let params = vec![
+                    serde_json::to_value(payload_v3)?,
+                    serde_json::to_value(versioned_hashes)?,
+                    serde_json::to_value(parent_beacon_block_root)?,
+                ];
+                self.execute_on(endpoint, "engine_newPayloadV3", params, timeout)
+                    .await
+            }
+            _ => bail!(Error::InvalidParameters),
+        }
+    }
+
+    /// Like [`Eth1Api::new_payload`], but first checks that the parent of `payload` is known to
+    /// the execution client.
+    ///
+    /// Submitting a payload with an unknown parent makes the execution client respond with
+    /// `SYNCING`, which is not useful when the caller only wants to validate the payload.
+    pub async fn new_payload_checked<P: Preset>(
+        &self,
+        payload: ExecutionPayload<P>,
+        params: Option<ExecutionPayloadParams>,
+    ) -> Result<PayloadStatusV1> {
+        let parent_hash = payload.parent_hash();
+
+        let parent = self
+            .request_with_fallback(|(api, headers)| Ok(api.block(parent_hash.into(), headers)))
+            .await?;
+
+        ensure!(parent.is_some(), Error::UnknownParent { parent_hash });
+
+        self.new_payload(payload, params).await
+    }
+
+    /// Calls [`engine_forkchoiceUpdatedV1`] or [`engine_forkchoiceUpdatedV2`] or [`engine_forkchoiceUpdatedV3`] depending on `payload_attributes`.
+    ///
+    /// Later versions of `engine_forkchoiceUpdated` accept parameters of all prior versions,
+    /// but using the earlier versions allows the application to work with old execution clients.
+    ///
+    /// [`engine_forkchoiceUpdatedV1`]: https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/paris.md#engine_forkchoiceupdatedv1
+    /// [`engine_forkchoiceUpdatedV2`]: https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/shanghai.md#engine_forkchoiceupdatedv2
+    /// [`engine_forkchoiceUpdatedV3`]: https://github.com/ethereum/execution-apis/blob/a0d03086564ab1838b462befbc083f873dcf0c0f/src/engine/cancun.md#engine_forkchoiceupdatedv3
+    pub async fn forkchoice_updated<P: Preset>(
+        &self,
+        head_block_hash: ExecutionBlockHash,
+        safe_block_hash: ExecutionBlockHash,
+        finalized_block_hash: ExecutionBlockHash,
+        payload_attributes: Either<Phase, PayloadAttributes<P>>,
+    ) -> Result<ForkChoiceUpdatedResponse> {
+        self.forkchoice_updated_with_timeout(
+            head_block_hash,
+            safe_block_hash,
+            finalized_block_hash,
+            payload_attributes,
+            None,
+        )
+        .await
+    }
+
+    /// Like [`Eth1Api::forkchoice_updated`], but `timeout` overrides the default timeout for
+    /// this call if present.
+    pub async fn forkchoice_updated_with_timeout<P: Preset>(
+        &self,
+        head_block_hash: ExecutionBlockHash,
+        safe_block_hash: ExecutionBlockHash,
+        finalized_block_hash: ExecutionBlockHash,
+        payload_attributes: Either<Phase, PayloadAttributes<P>>,
+        timeout: Option<Duration>,
+    ) -> Result<ForkChoiceUpdatedResponse> {
+        let timeout = timeout.unwrap_or(ENGINE_FORKCHOICE_UPDATED_TIMEOUT);
+
+        let fork_choice_state = ForkChoiceStateV1 {
+            head_block_hash,
+            safe_block_hash,
+            finalized_block_hash,
+        };
+
+        let phase = payload_attributes
+            .as_ref()
+            .either(CopyExt::copy, PayloadAttributes::phase);
+
+        let params = vec![
+            serde_json::to_value(fork_choice_state)?,
+            self.payload_attributes_to_value(payload_attributes.right())?,
+        ];
+
+        let (
+            RawForkChoiceUpdatedResponse {
+                payload_id,
+                payload_status,
+            },
+            report,
+        ) = match phase {
+            Phase::Bellatrix => {
+                let (result, report) = self
+                    .execute_with_report(
+                        None,
+                        "engine_forkchoiceUpdatedV1",
+                        params.clone(),
+                        Some(timeout),
+                    )
+                    .await;
+
+                // `engine_forkchoiceUpdatedV2` accepts `PayloadAttributesV1`.
+                match result {
+                    Err(error) if is_method_not_found(&error) => {
+                        warn!(
+                            "execution client does not support engine_forkchoiceUpdatedV1; \
+                             retrying with engine_forkchoiceUpdatedV2",
+                        );
+
+                        let (result, report) = self
+                            .execute_with_report(
+                                None,
+                                "engine_forkchoiceUpdatedV2",
+                                params,
+                                Some(timeout),
+                            )
+                            .await;
+
+                        (result?, report)
+                    }
+                    result => (result?, report),
+                }
+            }
+            Phase::Capella => {
+                let (result, report) = self
+                    .execute_with_report(None, "engine_forkchoiceUpdatedV2", params, Some(timeout))
+                    .await;
+
+                (result?, report)
+            }
+            Phase::Deneb => {
+                let (result, report) = self
+                    .execute_with_report(None, "engine_forkchoiceUpdatedV3", params, Some(timeout))
+                    .await;
+
+                (result?, report)
+            }
+            _ => {
+                // This match arm will silently match any new phases.
+                // Cause a compilation error if a new phase is added.
+                const_assert_eq!(Phase::CARDINALITY, 5);
+
+                bail!(Error::PhasePreBellatrix)
+            }
+        };
+
+        // Execution clients only start building payloads for valid heads.
+        // An ID returned with any other status cannot be used to get a payload.
+        let payload_id = payload_id.filter(|payload_id| {
+            let valid = payload_status.status.is_valid();
+
+            if !valid {
+                warn!(
+                    "execution client returned payload ID {payload_id:?} with status {:?}; \
+                     ignoring it",
+                    payload_status.status,
+                );
+            }
+
+            valid
+        });
+
+        let payload_id = match phase {
+            Phase::Bellatrix => payload_id.map(PayloadId::Bellatrix),
+            Phase::Capella => payload_id.map(PayloadId::Capella),
+            Phase::Deneb => payload_id.map(PayloadId::Deneb),
+            _ => {
+                // This match arm will silently match any new phases.
+                // Cause a compilation error if a new phase is added.
+                const_assert_eq!(Phase::CARDINALITY, 5);
+
+                bail!(Error::PhasePreBellatrix)
+            }
+        };
+
+        if let (true, Some(payload_id), Some(endpoint)) =
+            (self.pin_payload_endpoints, payload_id, report.served_by)
+        {
+            let mut pinned_payload_endpoints = self.pinned_payload_endpoints.lock().await;
+
+            if pinned_payload_endpoints.len() == MAX_PINNED_PAYLOAD_IDS {
+                pinned_payload_endpoints.pop_front();
+            }
+
+            pinned_payload_endpoints.push_back((payload_id, endpoint));
+        }
+
+        if payload_status.status.is_invalid() {
+            warn!(
+                "execution client rejected forkchoice state (head block hash: {head_block_hash:?}, \
+                 status: {:?}, validation error: {})",
+                payload_status.status,
+                payload_status.validation_error.as_deref().unwrap_or("none"),
+            );
+        }
+
+        Ok(ForkChoiceUpdatedResponse {
+            payload_status,
+            payload_id,
+        })
+    }
+
+    /// Computes the payload timestamp for a proposal at `slot` and checks that it is later than
+    /// the timestamp of the head block.
+    ///
+    /// Like in [`Eth1Api::forkchoice_updated_checked`], the check is skipped if the head block is
+    /// not known to the execution client.
+    pub async fn payload_timestamp_at_slot(
+        &self,
+        genesis_time: UnixSeconds,
+        slot: Slot,
+        head_block_hash: ExecutionBlockHash,
+    ) -> Result<UnixSeconds> {
+        let timestamp =
+            misc::compute_timestamp_at_slot_from_genesis_time(&self.config, genesis_time, slot);
+
+        self.ensure_timestamp_after_head(head_block_hash, timestamp)
+            .await?;
+
+        Ok(timestamp)
+    }
+
+    /// Like [`Eth1Api::forkchoice_updated`], but first checks that `payload_attributes` are
+    /// consistent with the head block.
+    ///
+    /// Execution clients respond to malformed payload attributes with errors that are hard to
+    /// interpret. The timestamp check is skipped if the head block is not known to the execution
+    /// client, in which case it will respond with `SYNCING` anyway.
+    pub async fn forkchoice_updated_checked<P: Preset>(
+        &self,
+        head_block_hash: ExecutionBlockHash,
+        safe_block_hash: ExecutionBlockHash,
+        finalized_block_hash: ExecutionBlockHash,
+        payload_attributes: Either<Phase, PayloadAttributes<P>>,
+    ) -> Result<ForkChoiceUpdatedResponse> {
+        if let Some(payload_attributes) = payload_attributes.as_ref().right() {
+            self.validate_payload_attributes(head_block_hash, payload_attributes)
+                .await?;
+        }
+
+        self.forkchoice_updated(
+            head_block_hash,
+            safe_block_hash,
+            finalized_block_hash,
+            payload_attributes,
+        )
+        .await
+    }
+
+    /// Calls `engine_forkchoiceUpdated` without payload attributes.
+    ///
+    /// Meant to be called when a scheduled proposal is abandoned (e.g., after a reorg) so that the
+    /// execution client is not left building a payload that will never be requested.
+    /// `head_phase` must be the phase of the head block and determines the method version.
+    pub async fn cancel_payload_building<P: Preset>(
+        &self,
+        head_block_hash: ExecutionBlockHash,
+        safe_block_hash: ExecutionBlockHash,
+        finalized_block_hash: ExecutionBlockHash,
+        head_phase: Phase,
+    ) -> Result<ForkChoiceUpdatedResponse> {
+        self.forkchoice_updated::<P>(
+            head_block_hash,
+            safe_block_hash,
+            finalized_block_hash,
+            Either::Left(head_phase),
+        )
+        .await
+    }
+
+    /// Calls [`engine_exchangeTransitionConfigurationV1`] and returns the terminal PoW block
+    /// parameters the execution client is configured with.
+    ///
+    /// Logs a warning if the terminal total difficulty or terminal block hash differ from the ones
+    /// in [`Config`]. Mismatches are common on devnets with misconfigured merge parameters.
+    /// Terminal block numbers are not compared because [`Config`] does not contain one.
+    ///
+    /// [`Config`]: types::config::Config
+    /// [`engine_exchangeTransitionConfigurationV1`]: https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/paris.md#engine_exchangetransitionconfigurationv1
+    pub async fn get_terminal_total_difficulty(&self) -> Result<TransitionConfigurationV1> {
+        let expected = TransitionConfigurationV1 {
+            terminal_total_difficulty: self.config.terminal_total_difficulty,
+            terminal_block_hash: self.config.terminal_block_hash,
+            terminal_block_number: 0,
+        };
+
+        let params = vec![serde_json::to_value(expected)?];
+
+        let actual: TransitionConfigurationV1 = self
+            .execute(
+                "engine_exchangeTransitionConfigurationV1",
+                params,
+                Some(ENGINE_EXCHANGE_TRANSITION_CONFIGURATION_TIMEOUT),
+            )
+            .await?;
+
+        if actual.terminal_total_difficulty != expected.terminal_total_difficulty
+            || actual.terminal_block_hash != expected.terminal_block_hash
+        {
+            warn!(
+                "execution client transition configuration does not match beacon node \
+                 configuration (execution client: {actual:?}, beacon node: {expected:?})",
+            );
+        }
+
+        Ok(actual)
+    }
+
+    /// Calls [`engine_getPayloadV1`] or [`engine_getPayloadV2`] or [`engine_getPayloadV3`]depending on `payload_id`.
+    ///
+    /// Newer versions of the method may be used to request payloads from all prior versions,
+    /// but using the old methods allows the application to work with old execution clients.
+    ///
+    /// [`engine_getPayloadV1`]: https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/paris.md#engine_getpayloadv1
+    /// [`engine_getPayloadV2`]: https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/shanghai.md#engine_getpayloadv2
+    /// [`engine_getPayloadV3`]: https://github.com/ethereum/execution-apis/blob/a0d03086564ab1838b462befbc083f873dcf0c0f/src/engine/cancun.md#engine_getpayloadv3
+    pub async fn get_payload<P: Preset>(
+        &self,
+        payload_id: PayloadId,
+    ) -> Result<WithBlobsAndMev<ExecutionPayload<P>, P>> {
+        self.get_payload_with_timeout(payload_id, None).await
+    }
+
+    /// Like [`Eth1Api::get_payload`], but `timeout` overrides the default timeout for this call
+    /// if present. The timeout applies to each attempt separately.
+    pub async fn get_payload_with_timeout<P: Preset>(
+        &self,
+        payload_id: PayloadId,
+        timeout: Option<Duration>,
+    ) -> Result<WithBlobsAndMev<ExecutionPayload<P>, P>> {
+        let timeout = timeout.unwrap_or(ENGINE_GET_PAYLOAD_TIMEOUT);
+
+        let pinned_endpoint = self
+            .pinned_payload_endpoints
+            .lock()
+            .await
+            .iter()
+            .rev()
+            .find(|(pinned_id, _)| *pinned_id == payload_id)
+            .map(|(_, endpoint)| endpoint.clone());
+
+        let Some(endpoint) = pinned_endpoint else {
+            return self
+                .get_payload_from_endpoint(None, payload_id, timeout)
+                .await;
+        };
+
+        let mut attempts = 1;
+
+        loop {
+            match self
+                .get_payload_from_endpoint(Some(&endpoint), payload_id, timeout)
+                .await
+            {
+                // Errors from the execution client itself (e.g. an unknown payload ID)
+                // would only be repeated.
+                Err(error)
+                    if attempts < MAX_PINNED_GET_PAYLOAD_ATTEMPTS && is_transport_error(&error) =>
+                {
+                    warn!(
+                        "failed to get payload {payload_id:?} from Eth1 RPC endpoint {endpoint} \
+                         that issued it: {error}; retrying",
+                    );
+
+                    attempts += 1;
+                }
+                result => {
+                    return result.map_err(|error| {
+                        error.context(Error::PayloadIdLost {
+                            endpoint,
+                            payload_id,
+                        })
+                    })
+                }
+            }
+        }
+    }
+
+    /// Requests payloads from multiple execution clients and returns the most valuable one.
+    ///
+    /// Each payload ID must be paired with the URL of the execution client that issued it.
+    /// The URLs must be among the configured endpoints.
+    /// Failed requests are logged and skipped. An error is only returned if all of them fail.
+    /// Payloads without a block value (Bellatrix ones) are treated as being worth nothing.
+    pub async fn get_best_payload<P: Preset>(
+        &self,
+        payload_ids: Vec<(Url, PayloadId)>,
+    ) -> Result<WithBlobsAndMev<ExecutionPayload<P>, P>> {
+        // Requests carry JWT tokens, so they must not be sent to arbitrary URLs.
+        for (url, _) in &payload_ids {
+            ensure!(
+                self.original.contains(url),
+                Error::UnknownEndpoint { url: url.clone() },
+            );
+        }
+
+        let results = future::join_all(payload_ids.iter().map(|(url, payload_id)| {
+            self.get_payload_from_endpoint::<P>(Some(url), *payload_id, ENGINE_GET_PAYLOAD_TIMEOUT)
+        }))
+        .await;
+
+        payload_ids
+            .iter()
+            .zip(results)
+            .filter_map(|((url, payload_id), result)| {
+                result
+                    .map_err(|error| {
+                        warn!(
+                            "failed to get payload {payload_id:?} from Eth1 RPC endpoint {url}: \
+                             {error}",
+                        )
+                    })
+                    .ok()
+            })
+            .max_by_key(|payload| payload.mev.unwrap_or_default())
+            .ok_or_else(|| Error::NoPayloadsRetrieved.into())
+    }
+
+    pub(crate) async fn get_payload_from_endpoint<P: Preset>(
+        &self,
+        endpoint: Option<&Url>,
+        payload_id: PayloadId,
+        timeout: Duration,
+    ) -> Result<WithBlobsAndMev<ExecutionPayload<P>, P>> {
+        match payload_id {
+            PayloadId::Bellatrix(payload_id) => {
+                let params = vec![serde_json::to_value(payload_id)?];
+
+                let (result, _) = self
+                    .execute_with_report::<EngineGetPayloadV1Response<P>>(
+                        endpoint,
+                        "engine_getPayloadV1",
+                        params.clone(),
+                        Some(timeout),
+                    )
+                    .await;
+
+                // `engine_getPayloadV2` accepts Bellatrix payload IDs.
+                match result {
+                    Err(error) if is_method_not_found(&error) => {
+                        warn!(
+                            "execution client does not support engine_getPayloadV1; \
+                             retrying with engine_getPayloadV2",
+                        );
+
+                        let (result, _) = self
+                            .execute_with_report::<EngineGetPayloadV2BellatrixResponse<P>>(
+                                endpoint,
+                                "engine_getPayloadV2",
+                                params,
+                                Some(timeout),
+                            )
+                            .await;
+
+                        result.map(Into::into)
+                    }
+                    result => result.map(Into::into),
+                }
+            }
+            PayloadId::Capella(payload_id) => {
+                let params = vec![serde_json::to_value(payload_id)?];
+
+                let (result, _) = self
+                    .execute_with_report::<EngineGetPayloadV2Response<P>>(
+                        endpoint,
+                        "engine_getPayloadV2",
+                        params,
+                        Some(timeout),
+                    )
+                    .await;
+
+                result.map(Into::into)
+            }
+            PayloadId::Deneb(payload_id) => {
+                let params = vec![serde_json::to_value(payload_id)?];
+
+                let (result, _) = self
+                    .execute_with_report::<EngineGetPayloadV3Response<P>>(
+                        endpoint,
+                        "engine_getPayloadV3",
+                        params,
+                        Some(timeout),
+                    )
+                    .await;
+
+                result.map(Into::into)
+            }
+        }
+    }
+
+    async fn validate_payload_attributes<P: Preset>(
+        &self,
+        head_block_hash: ExecutionBlockHash,
+        payload_attributes: &PayloadAttributes<P>,
+    ) -> Result<()> {
+        if let Some(withdrawals) = payload_attributes.withdrawals() {
+            for (previous, withdrawal) in withdrawals.iter().zip(withdrawals.iter().skip(1)) {
+                ensure!(
+                    previous.index.checked_add(1) == Some(withdrawal.index),
+                    Error::InvalidPayloadAttributes(
+                        PayloadAttributesError::WithdrawalIndicesNotConsecutive {
+                            previous_index: previous.index,
+                            index: withdrawal.index,
+                        },
+                    ),
+                );
+            }
+        }
+
+        self.ensure_timestamp_after_head(head_block_hash, payload_attributes.timestamp())
+            .await
+    }
+
+    async fn ensure_timestamp_after_head(
+        &self,
+        head_block_hash: ExecutionBlockHash,
+        timestamp: UnixSeconds,
+    ) -> Result<()> {
+        let head = self
+            .request_with_fallback(|(api, headers)| Ok(api.block(head_block_hash.into(), headers)))
+            .await?;
+
+        if let Some(head) = head {
+            // `<U256 as TryInto<UnixSeconds>>::Error` is `&'static str`.
+            let head_timestamp = head.timestamp.try_into().map_err(AnyhowError::msg)?;
+
+            ensure!(
+                timestamp > head_timestamp,
+                Error::InvalidPayloadAttributes(PayloadAttributesError::TimestampNotLater {
+                    timestamp,
+                    head_timestamp,
+                }),
+            );
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(feature = "optimism"), allow(clippy::unused_self))]
+    fn payload_attributes_to_value<P: Preset>(
+        &self,
+        payload_attributes: Option<PayloadAttributes<P>>,
+    ) -> Result<Value> {
+        #[cfg_attr(not(feature = "optimism"), allow(unused_mut))]
+        let mut value = serde_json::to_value(payload_attributes)?;
+
+        #[cfg(feature = "optimism")]
+        if let (Value::Object(fields), Some(optimism_payload_attributes)) =
+            (&mut value, self.optimism_payload_attributes.as_ref())
+        {
+            if let Value::Object(extra_fields) = serde_json::to_value(optimism_payload_attributes)?
+            {
+                fields.extend(extra_fields);
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn new_payload_timeout(&self, phase: Phase) -> Duration {
+        self.new_payload_timeouts
+            .get(&phase)
+            .copied()
+            .unwrap_or(ENGINE_NEW_PAYLOAD_TIMEOUT)
+    }
+}
+
+pub(crate) fn is_fatal_invalid_payload(error: &AnyhowError) -> bool {
+    matches!(
+        error.downcast_ref(),
+        Some(Error::FatalInvalidPayload { .. })
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawForkChoiceUpdatedResponse {
+    payload_status: PayloadStatusV1,
+    payload_id: Option<H64>,
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum PayloadAttributesError {
+    #[error("timestamp {timestamp} is not later than head block timestamp {head_timestamp}")]
+    TimestampNotLater {
+        timestamp: UnixSeconds,
+        head_timestamp: UnixSeconds,
+    },
+    #[error("withdrawal index {index} does not follow {previous_index}")]
+    WithdrawalIndicesNotConsecutive {
+        previous_index: WithdrawalIndex,
+        index: WithdrawalIndex,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Instant};
+
+    use execution_engine::{PayloadAttributesV2, PayloadValidationStatus, WithdrawalV1};
+    use hex_literal::hex;
+    use httpmock::{Method, MockServer};
+    use prometheus_metrics::Metrics;
+    use reqwest::Client;
+    use serde_json::json;
+    use ssz::ContiguousList;
+    use std_ext::ArcExt as _;
+    use test_case::test_case;
+    use tokio::time;
+    use types::{
+        capella::containers::ExecutionPayload as CapellaExecutionPayload,
+        config::Config,
+        deneb::containers::ExecutionPayload as DenebExecutionPayload,
+        phase0::primitives::{ExecutionAddress, H256},
+        preset::Mainnet,
+    };
+
+    use crate::eth1_api::helpers::{
+        capella_get_payload_response, default_eth1_api, default_payload,
+        default_payload_attributes, eth1_api_with_urls, eth1_block_json,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bellatrix_payload_deserialization_with_real_response() -> Result<()> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "parentHash": "0x2c7776c6c6c4a3fa2fbfc4886930e681fe4658e23e988b7ce27d4f355269b4a4",
+                "feeRecipient": "0x0000000000000000000000000000000000000000",
+                "stateRoot": "0xdeb98cee0497b499dc1a6a2323f990d350e80301fbbb0e778b62b5037fce5bf6",
+                "receiptsRoot": "0x06215fe5ec9a1b418434561323471cc1c8cfc6ae121aaf03825596268581e098",
+                "logsBloom": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+                "prevRandao": "0x9ed233232634a35bbcb77b50cf357defc8f61ad5c74d5bba528e1a260f8d2f7f",
+                "blockNumber": "0x7130a",
+                "gasLimit": "0xf78798",
+                "gasUsed": "0x14820",
+                "timestamp": "0x621cc4f8",
+                "extraData": "0xd883010a11846765746888676f312e31372e38856c696e7578",
+                "baseFeePerGas": "0x7",
+                "blockHash": "0xedd9cf26b9a0455a67e9abefe926796356ca6564d02463e229097c61ced696db",
+                "transactions": [
+                    "0xf86e078459682f0782520894419f2d6c3f5fe8bf43f91923ba21e996032897298894a1739b5e1d49c8808328d2f0a069dffffc6f9b20157bd17872d326de8ed088de3e24f2801dd9375ddbecd013f0a041aab6f5dff83fdd2595cc55725b28128b8902f12f3db598dce9f9183f989300",
+                    "0x02f87883146966830516988459682f008459682f078252089432960b83199ae0f78756dbcf016a8e88e4dd7a748894a19041886f000080c001a0f916421115b1dc667b959fe32fa01cc9ba07942078b9e28435fd0a55c1cbf2dba076da1b6e79fa9a3b6b77e1601546fa194652a3f9a73919c470254833dfae68f8",
+                    "0x02f87883146966830516998459682f008459682f0782520894b467d5ec9f6db8b1c156d40e65ebf88b2596ab198894a19041886f000080c001a0cc9ddcece6913c48e3aaaab25fb4f98da8540f1ffac58b010c9d3d0c60e01edba073cbf451658aa60dac89b62a463a2a95cdba3c73e5d258f80eedd6d465ab0772",
+                    "0x02f878831469668305169a8459682f008459682f078252089477831a3a5552ad92848d7134a1e467c1089fb04a8894a19041886f000080c001a0f3687841790c73693a44710dfc83d02f7044ea821cca5a66b92b283c2c346d62a013695d07f9e62132a8c3c423dc4b74b914b5fa758f88fcf2ed10242aaa68ca6a",
+                ],
+            },
+        });
+
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200).body(body.to_string());
+        });
+
+        // The block seems to be from the Kintsugi testnet. There's no block explorer still serving
+        // Kintsugi blocks to confirm it, but the block number and timestamp suggest that execution
+        // layer genesis happened around 2021-12-15, just before the `MIN_GENESIS_TIME` of Kintsugi.
+        let config = Arc::new(Config::mainnet());
+        let auth = Arc::default();
+        let server_url = server.url("/").parse()?;
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config,
+            Client::new(),
+            auth,
+            vec![server_url],
+            None,
+            None,
+        ));
+
+        let payload_id = PayloadId::Bellatrix(H64(hex!("a5f7426cdca69a73")));
+
+        eth1_api.get_payload::<Mainnet>(payload_id).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_capella_payload_deserialization_with_full_response() -> Result<()> {
+        let body = capella_get_payload_response(
+            "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        );
+
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200).body(body.to_string());
+        });
+
+        let config = Arc::new(Config::withdrawal_devnet_4());
+        let auth = Arc::default();
+        let server_url = server.url("/").parse()?;
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config,
+            Client::new(),
+            auth,
+            vec![server_url],
+            None,
+            None,
+        ));
+
+        let payload_id = PayloadId::Capella(H64(hex!("a5f7426cdca69a73")));
+        let payload = eth1_api.get_payload::<Mainnet>(payload_id).await?;
+
+        assert_eq!(payload.value.phase(), Phase::Capella);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_best_payload_selects_highest_block_value() -> Result<()> {
+        let low_value_server = MockServer::start();
+        let high_value_server = MockServer::start();
+        let failing_server = MockServer::start();
+
+        low_value_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200)
+                .body(capella_get_payload_response("0x1").to_string());
+        });
+
+        high_value_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200)
+                .body(capella_get_payload_response("0x2").to_string());
+        });
+
+        failing_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(500);
+        });
+
+        let config = Arc::new(Config::withdrawal_devnet_4());
+        let auth = Arc::default();
+        let low_value_url: Url = low_value_server.url("/").parse()?;
+        let high_value_url: Url = high_value_server.url("/").parse()?;
+        let failing_url: Url = failing_server.url("/").parse()?;
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config,
+            Client::new(),
+            auth,
+            vec![
+                low_value_url.clone(),
+                high_value_url.clone(),
+                failing_url.clone(),
+            ],
+            None,
+            None,
+        ));
+
+        let payload_id = PayloadId::Capella(H64(hex!("a5f7426cdca69a73")));
+
+        let payload = eth1_api
+            .get_best_payload::<Mainnet>(vec![
+                (failing_url.clone(), payload_id),
+                (low_value_url, payload_id),
+                (high_value_url, payload_id),
+            ])
+            .await?;
+
+        assert_eq!(payload.mev, Some(2.into()));
+
+        // Requests to specific endpoints are recorded like any others.
+        assert_eq!(eth1_api.recent_errors(&failing_url).await.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_best_payload_rejects_unknown_endpoints() -> Result<()> {
+        let configured_server = MockServer::start();
+        let unknown_server = MockServer::start();
+
+        let configured_mock = configured_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200)
+                .body(capella_get_payload_response("0x1").to_string());
+        });
+
+        let unknown_mock = unknown_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200)
+                .body(capella_get_payload_response("0x2").to_string());
+        });
+
+        let configured_url = configured_server.url("/").parse()?;
+        let unknown_url: Url = unknown_server.url("/").parse()?;
+        let eth1_api = eth1_api_with_urls(vec![configured_url.clone()]);
+        let payload_id = PayloadId::Capella(H64(hex!("a5f7426cdca69a73")));
+
+        let error = eth1_api
+            .get_best_payload::<Mainnet>(vec![
+                (configured_url, payload_id),
+                (unknown_url.clone(), payload_id),
+            ])
+            .await
+            .expect_err("unknown endpoints should be rejected");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::UnknownEndpoint { url }) if *url == unknown_url,
+        ));
+
+        configured_mock.assert_hits(0);
+        unknown_mock.assert_hits(0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_valid_payload_status_deserialization() -> Result<()> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "result": {
+                "status": "VALID",
+                "latestValidHash": "0x0da76c72389ffe8b8bef1266213dd0dc4bf7030293913bfd69869cb349b13d35",
+                "validationError": null,
+            },
+        });
+
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200).body(body.to_string());
+        });
+
+        let config = Arc::new(Config::mainnet());
+        let auth = Arc::default();
+        let server_url = server.url("/").parse()?;
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config,
+            Client::new(),
+            auth,
+            vec![server_url],
+            None,
+            None,
+        ));
+
+        let actual_status = eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await?;
+
+        let expected_status = PayloadStatusV1 {
+            status: PayloadValidationStatus::Valid,
+            latest_valid_hash: Some(H256(hex!(
+                "0da76c72389ffe8b8bef1266213dd0dc4bf7030293913bfd69869cb349b13d35"
+            ))),
+            validation_error: None,
+        };
+
+        assert_eq!(actual_status, expected_status);
+
+        Ok(())
+    }
+
+    // `geth` responds to invalid payloads with objects containing `method` and `params`.
+    // We had to fork `jsonrpc` because it does not allow nonstandard members.
+    #[tokio::test]
+    async fn test_invalid_payload_status_deserialization() -> Result<()> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": "",
+            "params": null,
+            "id": 0,
+            "result": {
+                "latestValidHash": "0x5669a0cec34c19c288b9db210ea180d11ad3d92975234bdc769610b5fa4d7f80",
+                "status": "INVALID",
+                "validationError": null,
+            },
+        });
+
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200).body(body.to_string());
+        });
+
+        let config = Arc::new(Config::mainnet());
+        let auth = Arc::default();
+        let server_url = server.url("/").parse()?;
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config,
+            Client::new(),
+            auth,
+            vec![server_url],
+            None,
+            None,
+        ));
+
+        let actual_status = eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await?;
+
+        let expected_status = PayloadStatusV1 {
+            status: PayloadValidationStatus::Invalid,
+            latest_valid_hash: Some(H256(hex!(
+                "5669a0cec34c19c288b9db210ea180d11ad3d92975234bdc769610b5fa4d7f80"
+            ))),
+            validation_error: None,
+        };
+
+        assert_eq!(actual_status, expected_status);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_payload_returns_accepted_status() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "ACCEPTED",
+                        "latestValidHash": null,
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let payload_status = eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await?;
+
+        assert_eq!(payload_status.status, PayloadValidationStatus::Accepted);
+        assert!(payload_status.is_optimistic());
+        assert!(!payload_status.status.is_valid());
+        assert!(!payload_status.status.is_invalid());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_engine_call_observer_records_new_payload() -> Result<()> {
+        #[derive(Default)]
+        struct RecordingObserver {
+            calls: std::sync::Mutex<Vec<(String, bool, usize)>>,
+        }
+
+        impl EngineCallObserver for RecordingObserver {
+            fn observe(&self, call: &EngineCall) {
+                self.calls
+                    .lock()
+                    .expect("mutex should not be poisoned")
+                    .push((call.method.to_owned(), call.succeeded, call.endpoints_tried));
+            }
+        }
+
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": H256::zero(),
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let observer = Arc::new(RecordingObserver::default());
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?])
+            .with_engine_call_observer(observer.clone_arc());
+
+        eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await?;
+
+        assert_eq!(
+            *observer.calls.lock().expect("mutex should not be poisoned"),
+            [("engine_newPayloadV1".to_owned(), true, 1)],
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recent_engine_calls_keeps_most_recent_calls() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": H256::zero(),
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV1");
+
+            then.status(500);
+        });
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_blockNumber");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": "0x64",
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api =
+            eth1_api_with_urls(vec![server.url("/").parse()?]).with_recent_engine_calls(2);
+
+        for _ in 0..2 {
+            eth1_api
+                .new_payload::<Mainnet>(default_payload(), None)
+                .await?;
+        }
+
+        eth1_api
+            .forkchoice_updated::<Mainnet>(
+                H256::zero(),
+                H256::zero(),
+                H256::zero(),
+                Either::Left(Phase::Bellatrix),
+            )
+            .await
+            .expect_err("endpoint should fail");
+
+        // Calls outside the Engine API are not recorded.
+        eth1_api.current_head_number().await?;
+
+        let records = eth1_api.recent_engine_calls().await;
+
+        let summary = records
+            .iter()
+            .map(|record| (record.method.as_str(), record.error.is_some()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            [
+                ("engine_newPayloadV1", false),
+                ("engine_forkchoiceUpdatedV1", true),
+            ],
+        );
+
+        assert!(records[0].timestamp <= records[1].timestamp);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_payload_uses_timeout_override_for_phase() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV3");
+
+            then.status(200).delay(Duration::from_secs(2)).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": H256::zero(),
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?])
+            .with_new_payload_timeout(Phase::Deneb, Duration::from_millis(100));
+
+        let params = ExecutionPayloadParams::Deneb {
+            versioned_hashes: vec![],
+            parent_beacon_block_root: H256::zero(),
+        };
+
+        let start = Instant::now();
+
+        eth1_api
+            .new_payload::<Mainnet>(DenebExecutionPayload::default().into(), Some(params))
+            .await
+            .expect_err("request should time out before the response arrives");
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_per_call_timeouts_override_defaults() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV2");
+
+            then.status(200).delay(Duration::from_secs(2)).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": H256::zero(),
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_getPayloadV2");
+
+            then.status(200)
+                .delay(Duration::from_secs(2))
+                .body(capella_get_payload_response("0x1").to_string());
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let timeout = Some(Duration::from_millis(100));
+        let start = Instant::now();
+
+        eth1_api
+            .new_payload_with_timeout::<Mainnet>(
+                CapellaExecutionPayload::default().into(),
+                None,
+                timeout,
+            )
+            .await
+            .expect_err("engine_newPayloadV2 should time out before the response arrives");
+
+        eth1_api
+            .get_payload_with_timeout::<Mainnet>(PayloadId::Capella(H64::zero()), timeout)
+            .await
+            .expect_err("engine_getPayloadV2 should time out before the response arrives");
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_payload_records_method_version() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV3");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": H256::zero(),
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let metrics = Arc::new(Metrics::new()?);
+
+        let eth1_api = Eth1Api::new(
+            Arc::new(Config::mainnet()),
+            Client::new(),
+            Arc::default(),
+            vec![server.url("/").parse()?],
+            None,
+            Some(metrics.clone_arc()),
+        );
+
+        let params = ExecutionPayloadParams::Deneb {
+            versioned_hashes: vec![],
+            parent_beacon_block_root: H256::zero(),
+        };
+
+        eth1_api
+            .new_payload::<Mainnet>(DenebExecutionPayload::default().into(), Some(params))
+            .await?;
+
+        let count = |version| {
+            metrics
+                .eth1_api_engine_method_versions
+                .with_label_values(&["engine_newPayload", version])
+                .get()
+        };
+
+        assert_eq!(count("V3"), 1);
+        assert_eq!(count("V2"), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_payload_records_payload_status() -> Result<()> {
+        let metrics = Arc::new(Metrics::new()?);
+
+        for status in ["VALID", "INVALID"] {
+            let server = MockServer::start();
+
+            server.mock(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("engine_newPayloadV2");
+
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": {
+                            "status": status,
+                            "latestValidHash": H256::zero(),
+                            "validationError": null,
+                        },
+                    })
+                    .to_string(),
+                );
+            });
+
+            let eth1_api = Eth1Api::new(
+                Arc::new(Config::mainnet()),
+                Client::new(),
+                Arc::default(),
+                vec![server.url("/").parse()?],
+                None,
+                Some(metrics.clone_arc()),
+            );
+
+            eth1_api
+                .new_payload::<Mainnet>(CapellaExecutionPayload::default().into(), None)
+                .await?;
+        }
+
+        let count = |status| {
+            metrics
+                .eth1_api_payload_statuses
+                .with_label_values(&[status])
+                .get()
+        };
+
+        assert_eq!(count("VALID"), 1);
+        assert_eq!(count("INVALID"), 1);
+        assert_eq!(count("SYNCING"), 0);
+
+        Ok(())
+    }
+
+    #[test_case(false; "default")]
+    #[test_case(true; "fatal")]
+    #[tokio::test]
+    async fn test_new_payload_with_invalid_status(fatal: bool) -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV2");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "INVALID",
+                        "latestValidHash": H256::zero(),
+                        "validationError": "bad block",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let mut eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        if fatal {
+            eth1_api = eth1_api.with_fatal_invalid_payloads();
+        }
+
+        let result = eth1_api
+            .new_payload::<Mainnet>(CapellaExecutionPayload::default().into(), None)
+            .await;
+
+        if fatal {
+            let error = result.expect_err("INVALID status should be fatal");
+
+            assert!(matches!(
+                error.downcast_ref(),
+                Some(Error::FatalInvalidPayload { validation_error: Some(message), .. })
+                    if message == "bad block",
+            ));
+        } else {
+            assert!(result?.status.is_invalid());
+        }
+
+        Ok(())
+    }
+
+    #[test_case("VALID", "INVALID", true; "valid and invalid")]
+    #[test_case("INVALID", "VALID", true; "invalid and valid")]
+    #[test_case("VALID", "SYNCING", false; "valid and syncing")]
+    #[test_case("VALID", "VALID", false; "both valid")]
+    #[tokio::test]
+    async fn test_new_payload_cross_check(
+        first_status: &str,
+        second_status: &str,
+        diverged: bool,
+    ) -> Result<()> {
+        let first_server = MockServer::start();
+        let second_server = MockServer::start();
+
+        for (server, status) in [
+            (&first_server, first_status),
+            (&second_server, second_status),
+        ] {
+            server.mock(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("engine_newPayloadV1");
+
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": {
+                            "status": status,
+                            "latestValidHash": null,
+                            "validationError": null,
+                        },
+                    })
+                    .to_string(),
+                );
+            });
+        }
+
+        let first_url: Url = first_server.url("/").parse()?;
+        let second_url: Url = second_server.url("/").parse()?;
+        let eth1_api = eth1_api_with_urls(vec![first_url.clone(), second_url.clone()]);
+
+        let cross_check = eth1_api
+            .new_payload_cross_check::<Mainnet>(default_payload(), None, &first_url, &second_url)
+            .await?;
+
+        assert_eq!(cross_check.first.status.as_ref(), first_status);
+        assert_eq!(cross_check.second.status.as_ref(), second_status);
+        assert_eq!(cross_check.diverged, diverged);
+
+        Ok(())
+    }
+
+    #[test_case("0xc70d808a128d7380000", true; "matching")]
+    #[test_case("0x1", false; "mismatching")]
+    #[tokio::test]
+    async fn test_get_terminal_total_difficulty(
+        terminal_total_difficulty: &str,
+        matches_config: bool,
+    ) -> Result<()> {
+        let server = MockServer::start();
+
+        // The request should contain the terminal total difficulty from `Config::mainnet`.
+        let mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_exchangeTransitionConfigurationV1")
+                .body_contains("0xc70d808a128d7380000");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "terminalTotalDifficulty": terminal_total_difficulty,
+                        "terminalBlockHash": ExecutionBlockHash::zero(),
+                        "terminalBlockNumber": "0x0",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let transition_configuration = eth1_api.get_terminal_total_difficulty().await?;
+
+        mock.assert_hits(1);
+
+        assert_eq!(
+            transition_configuration.terminal_total_difficulty
+                == eth1_api.config.terminal_total_difficulty,
+            matches_config,
+        );
+        assert_eq!(
+            transition_configuration.terminal_block_hash,
+            ExecutionBlockHash::zero(),
+        );
+        assert_eq!(transition_configuration.terminal_block_number, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_payload_falls_back_to_v2_if_v1_is_not_supported() -> Result<()> {
+        let server = MockServer::start();
+
+        let v1_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "error": {
+                        "code": -32601,
+                        "message": "the method engine_newPayloadV1 does not exist/is not available",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let v2_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV2");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": H256::zero(),
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let payload_status = eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await?;
+
+        assert_eq!(payload_status.status, PayloadValidationStatus::Valid);
+
+        v1_mock.assert_hits(1);
+        v2_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_falls_back_to_v2_if_v1_is_not_supported() -> Result<()> {
+        let server = MockServer::start();
+
+        let v1_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_getPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "error": {
+                        "code": -32601,
+                        "message": "the method engine_getPayloadV1 does not exist/is not available",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        // Bellatrix payloads have no withdrawals.
+        let mut v2_response = capella_get_payload_response("0x2");
+
+        v2_response["result"]["executionPayload"]
+            .as_object_mut()
+            .expect("response should contain an execution payload")
+            .remove("withdrawals");
+
+        let v2_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_getPayloadV2");
+
+            then.status(200).body(v2_response.to_string());
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+        let payload = eth1_api
+            .get_payload::<Mainnet>(PayloadId::Bellatrix(H64::zero()))
+            .await?;
+
+        assert!(matches!(payload.value, ExecutionPayload::Bellatrix(_)));
+        assert_eq!(payload.mev, Some(2.into()));
+
+        v1_mock.assert_hits(1);
+        v2_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_payload_falls_back_to_endpoint_that_supports_method() -> Result<()> {
+        let primary_server = MockServer::start();
+        let fallback_server = MockServer::start();
+
+        let primary_mock = primary_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "error": {
+                        "code": -32601,
+                        "message": "the method engine_newPayloadV1 does not exist/is not available",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let fallback_v1_mock = fallback_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": H256::zero(),
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![
+            primary_server.url("/").parse()?,
+            fallback_server.url("/").parse()?,
+        ]);
+
+        let payload_status = eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await?;
+
+        assert_eq!(payload_status.status, PayloadValidationStatus::Valid);
+
+        // The V2 retry is only made if no endpoint supports V1.
+        primary_mock.assert_hits(1);
+        fallback_v1_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_payload_checked_with_unknown_parent() -> Result<()> {
+        let server = MockServer::start();
+
+        let get_block_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByHash");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": null,
+                })
+                .to_string(),
+            );
+        });
+
+        let new_payload_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "SYNCING",
+                        "latestValidHash": null,
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let config = Arc::new(Config::mainnet());
+        let auth = Arc::default();
+        let server_url = server.url("/").parse()?;
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config,
+            Client::new(),
+            auth,
+            vec![server_url],
+            None,
+            None,
+        ));
+
+        let error = eth1_api
+            .new_payload_checked::<Mainnet>(default_payload(), None)
+            .await
+            .expect_err("parent of payload should be unknown");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::UnknownParent { parent_hash }) if parent_hash.is_zero(),
+        ));
+
+        get_block_mock.assert();
+        new_payload_mock.assert_hits(0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_forkchoice_updated_checked_with_stale_timestamp() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByHash");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": eth1_block_json(100, H256::repeat_byte(1), H256::repeat_byte(2)),
+                })
+                .to_string(),
+            );
+        });
+
+        let forkchoice_updated_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV1");
+
+            then.status(200);
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let error = eth1_api
+            .forkchoice_updated_checked::<Mainnet>(
+                H256::repeat_byte(1),
+                H256::zero(),
+                H256::zero(),
+                Either::Right(default_payload_attributes()),
+            )
+            .await
+            .expect_err("payload attributes should have a stale timestamp");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::InvalidPayloadAttributes(
+                PayloadAttributesError::TimestampNotLater {
+                    timestamp: 1,
+                    head_timestamp: 0x63d2_af38,
+                },
+            )),
+        ));
+
+        forkchoice_updated_mock.assert_hits(0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_forkchoice_updated_checked_with_overflowing_withdrawal_index() -> Result<()> {
+        let server = MockServer::start();
+
+        let forkchoice_updated_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV2");
+
+            then.status(200);
+        });
+
+        let withdrawals = [WithdrawalIndex::MAX, 0]
+            .into_iter()
+            .map(|index| WithdrawalV1 {
+                index,
+                validator_index: 0,
+                address: ExecutionAddress::zero(),
+                amount: 0,
+            })
+            .collect::<Vec<_>>();
+
+        let payload_attributes = PayloadAttributes::Capella(PayloadAttributesV2 {
+            timestamp: 1,
+            prev_randao: H256::zero(),
+            suggested_fee_recipient: ExecutionAddress::zero(),
+            withdrawals: ContiguousList::try_from(withdrawals)?,
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let error = eth1_api
+            .forkchoice_updated_checked::<Mainnet>(
+                H256::repeat_byte(1),
+                H256::zero(),
+                H256::zero(),
+                Either::Right(payload_attributes),
+            )
+            .await
+            .expect_err("withdrawal indices should not be consecutive");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::InvalidPayloadAttributes(
+                PayloadAttributesError::WithdrawalIndicesNotConsecutive {
+                    previous_index: WithdrawalIndex::MAX,
+                    index: 0,
+                },
+            )),
+        ));
+
+        forkchoice_updated_mock.assert_hits(0);
+
+        Ok(())
+    }
+
+    // The head block in `eth1_block_json` has a timestamp of 1_674_751_800.
+    #[test_case(5_662_000, 1_674_768_023, true; "later than head")]
+    #[test_case(5_000_000, 1_666_824_023, false; "earlier than head")]
+    #[tokio::test]
+    async fn test_payload_timestamp_at_slot(
+        slot: Slot,
+        expected_timestamp: UnixSeconds,
+        later: bool,
+    ) -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_getBlockByHash");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": eth1_block_json(100, H256::repeat_byte(1), H256::repeat_byte(2)),
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let result = eth1_api
+            .payload_timestamp_at_slot(1_606_824_023, slot, H256::repeat_byte(1))
+            .await;
+
+        if later {
+            assert_eq!(result?, expected_timestamp);
+        } else {
+            let error = result.expect_err("payload timestamp should not be later than head");
+
+            assert!(matches!(
+                error.downcast_ref(),
+                Some(Error::InvalidPayloadAttributes(
+                    PayloadAttributesError::TimestampNotLater {
+                        timestamp,
+                        head_timestamp: 0x63d2_af38,
+                    },
+                )) if *timestamp == expected_timestamp,
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_forkchoice_updated_surfaces_validation_error() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "payloadStatus": {
+                            "status": "INVALID",
+                            "latestValidHash": H256::zero(),
+                            "validationError": "links to previously rejected block",
+                        },
+                        "payloadId": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let ForkChoiceUpdatedResponse {
+            payload_status,
+            payload_id,
+        } = eth1_api
+            .forkchoice_updated::<Mainnet>(
+                H256::repeat_byte(1),
+                H256::zero(),
+                H256::zero(),
+                Either::Left(Phase::Bellatrix),
+            )
+            .await?;
+
+        assert_eq!(payload_status.status, PayloadValidationStatus::Invalid);
+        assert_eq!(
+            payload_status.validation_error.as_deref(),
+            Some("links to previously rejected block"),
+        );
+        assert!(payload_id.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_forkchoice_updated_ignores_payload_id_for_syncing_head() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "payloadStatus": {
+                            "status": "SYNCING",
+                            "latestValidHash": null,
+                            "validationError": null,
+                        },
+                        "payloadId": "0x0000000000000001",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let ForkChoiceUpdatedResponse {
+            payload_status,
+            payload_id,
+        } = eth1_api
+            .forkchoice_updated::<Mainnet>(
+                H256::repeat_byte(1),
+                H256::zero(),
+                H256::zero(),
+                Either::Left(Phase::Bellatrix),
+            )
+            .await?;
+
+        assert_eq!(payload_status.status, PayloadValidationStatus::Syncing);
+        assert!(payload_id.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_payload_building_sends_null_payload_attributes() -> Result<()> {
+        let server = MockServer::start();
+
+        let forkchoice_updated_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV2")
+                .body_contains("},null]");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "payloadStatus": {
+                            "status": "VALID",
+                            "latestValidHash": H256::repeat_byte(1),
+                            "validationError": null,
+                        },
+                        "payloadId": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let ForkChoiceUpdatedResponse { payload_id, .. } = eth1_api
+            .cancel_payload_building::<Mainnet>(
+                H256::repeat_byte(1),
+                H256::zero(),
+                H256::zero(),
+                Phase::Capella,
+            )
+            .await?;
+
+        assert!(payload_id.is_none());
+
+        forkchoice_updated_mock.assert();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_retries_pinned_endpoint_after_timeout() -> Result<()> {
+        let primary_server = MockServer::start_async().await;
+        let pinned_server = MockServer::start_async().await;
+
+        primary_server
+            .mock_async(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("engine_forkchoiceUpdatedV2");
+
+                then.status(500);
+            })
+            .await;
+
+        let primary_get_payload_mock = primary_server
+            .mock_async(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("engine_getPayloadV2");
+
+                then.status(200)
+                    .body(capella_get_payload_response("0x1").to_string());
+            })
+            .await;
+
+        pinned_server
+            .mock_async(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("engine_forkchoiceUpdatedV2");
+
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": {
+                            "payloadStatus": {
+                                "status": "VALID",
+                                "latestValidHash": H256::repeat_byte(1),
+                                "validationError": null,
+                            },
+                            "payloadId": "0x0000000000000001",
+                        },
+                    })
+                    .to_string(),
+                );
+            })
+            .await;
+
+        let slow_get_payload_mock = pinned_server
+            .mock_async(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("engine_getPayloadV2");
+
+                then.status(200)
+                    .delay(ENGINE_GET_PAYLOAD_TIMEOUT * 2)
+                    .body(capella_get_payload_response("0x2").to_string());
+            })
+            .await;
+
+        let eth1_api = eth1_api_with_urls(vec![
+            primary_server.url("/").parse()?,
+            pinned_server.url("/").parse()?,
+        ])
+        .with_payload_endpoint_pinning();
+
+        let ForkChoiceUpdatedResponse { payload_id, .. } = eth1_api
+            .forkchoice_updated::<Mainnet>(
+                H256::repeat_byte(1),
+                H256::zero(),
+                H256::zero(),
+                Either::Left(Phase::Capella),
+            )
+            .await?;
+
+        let payload_id = payload_id.expect("execution client should return a payload ID");
+
+        // Reset the endpoints so that unpinned requests would go to the primary endpoint.
+        eth1_api
+            .set_active_endpoint(&primary_server.url("/").parse()?)
+            .await?;
+
+        let recover = async {
+            time::sleep(ENGINE_GET_PAYLOAD_TIMEOUT / 2).await;
+
+            slow_get_payload_mock.delete_async().await;
+
+            pinned_server
+                .mock_async(|when, then| {
+                    when.method(Method::POST)
+                        .path("/")
+                        .body_contains("engine_getPayloadV2");
+
+                    then.status(200)
+                        .body(capella_get_payload_response("0x2").to_string());
+                })
+                .await
+        };
+
+        let (payload, fast_get_payload_mock) =
+            tokio::join!(eth1_api.get_payload::<Mainnet>(payload_id), recover);
+
+        assert_eq!(payload?.mev, Some(2.into()));
+
+        primary_get_payload_mock.assert_hits_async(0).await;
+        fast_get_payload_mock.assert_hits_async(1).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_reports_lost_payload_id_if_pinned_endpoint_fails() -> Result<()> {
+        let primary_server = MockServer::start();
+        let pinned_server = MockServer::start();
+
+        primary_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV2");
+
+            then.status(500);
+        });
+
+        let primary_get_payload_mock = primary_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_getPayloadV2");
+
+            then.status(200)
+                .body(capella_get_payload_response("0x1").to_string());
+        });
+
+        pinned_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV2");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "payloadStatus": {
+                            "status": "VALID",
+                            "latestValidHash": H256::repeat_byte(1),
+                            "validationError": null,
+                        },
+                        "payloadId": "0x0000000000000001",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let pinned_get_payload_mock = pinned_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_getPayloadV2");
+
+            then.status(503);
+        });
+
+        let pinned_url: Url = pinned_server.url("/").parse()?;
+
+        let eth1_api =
+            eth1_api_with_urls(vec![primary_server.url("/").parse()?, pinned_url.clone()])
+                .with_payload_endpoint_pinning();
+
+        let ForkChoiceUpdatedResponse { payload_id, .. } = eth1_api
+            .forkchoice_updated::<Mainnet>(
+                H256::repeat_byte(1),
+                H256::zero(),
+                H256::zero(),
+                Either::Left(Phase::Capella),
+            )
+            .await?;
+
+        let payload_id = payload_id.expect("execution client should return a payload ID");
+
+        let error = eth1_api
+            .get_payload::<Mainnet>(payload_id)
+            .await
+            .expect_err("payload should not be requested from other endpoints");
+
+        let Some(Error::PayloadIdLost { endpoint, .. }) = error.downcast_ref() else {
+            panic!("unexpected error: {error:?}");
+        };
+
+        assert_eq!(endpoint, &pinned_url);
+
+        primary_get_payload_mock.assert_hits(0);
+        pinned_get_payload_mock.assert_hits(MAX_PINNED_GET_PAYLOAD_ATTEMPTS);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_is_pinned_to_endpoint_that_issued_payload_id() -> Result<()> {
+        let primary_server = MockServer::start();
+        let fallback_server = MockServer::start();
+
+        primary_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV2");
+
+            then.status(500);
+        });
+
+        let primary_get_payload_mock = primary_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_getPayloadV2");
+
+            then.status(200)
+                .body(capella_get_payload_response("0x1").to_string());
+        });
+
+        fallback_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV2");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "payloadStatus": {
+                            "status": "VALID",
+                            "latestValidHash": H256::repeat_byte(1),
+                            "validationError": null,
+                        },
+                        "payloadId": "0x0000000000000001",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        // Make the fallback endpoint fail once so that requests go back to the primary one.
+        fallback_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_blockNumber");
+
+            then.status(500);
+        });
+
+        let fallback_get_payload_mock = fallback_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_getPayloadV2");
+
+            then.status(200)
+                .body(capella_get_payload_response("0x2").to_string());
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![
+            primary_server.url("/").parse()?,
+            fallback_server.url("/").parse()?,
+        ])
+        .with_payload_endpoint_pinning();
+
+        let ForkChoiceUpdatedResponse { payload_id, .. } = eth1_api
+            .forkchoice_updated::<Mainnet>(
+                H256::repeat_byte(1),
+                H256::zero(),
+                H256::zero(),
+                Either::Left(Phase::Capella),
+            )
+            .await?;
+
+        let payload_id = payload_id.expect("execution client should return a payload ID");
+
+        eth1_api
+            .current_head_number()
+            .await
+            .expect_err("all endpoints should fail");
+
+        let payload = eth1_api.get_payload::<Mainnet>(payload_id).await?;
+
+        assert_eq!(payload.mev, Some(2.into()));
+
+        primary_get_payload_mock.assert_hits(0);
+        fallback_get_payload_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "optimism")]
+    #[test]
+    fn test_optimism_payload_attributes_are_serialized() -> Result<()> {
+        let optimism_payload_attributes = OptimismPayloadAttributes {
+            transactions: vec![],
+            no_tx_pool: true,
+            gas_limit: Some(U64::from(30_000_000)),
+        };
+
+        let eth1_api =
+            default_eth1_api().with_optimism_payload_attributes(optimism_payload_attributes);
+
+        let value = eth1_api.payload_attributes_to_value(Some(default_payload_attributes()))?;
+
+        assert_eq!(value["noTxPool"], json!(true));
+        assert_eq!(value["gasLimit"], json!("0x1c9c380"));
+        assert_eq!(value["transactions"], json!([]));
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "optimism"))]
+    #[test]
+    fn test_optimism_payload_attributes_are_not_serialized() -> Result<()> {
+        let value =
+            default_eth1_api().payload_attributes_to_value(Some(default_payload_attributes()))?;
+
+        assert_eq!(
+            value,
+            json!({
+                "timestamp": "0x1",
+                "prevRandao": H256::zero(),
+                "suggestedFeeRecipient": ExecutionAddress::zero(),
+            }),
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use execution_engine::{PayloadAttributes, PayloadAttributesV1};
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+use types::{
+    bellatrix::containers::ExecutionPayload as BellatrixExecutionPayload,
+    combined::ExecutionPayload,
+    config::Config,
+    phase0::primitives::{
+        DepositIndex, ExecutionAddress, ExecutionBlockHash, ExecutionBlockNumber, H256,
+    },
+    preset::Preset,
+};
+use web3::types::U64;
+
+use crate::{deposit_event::DepositEvent, eth1_api::api::Eth1Api};
+
+pub fn capella_get_payload_response(block_value: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "result": {
+            "executionPayload": {
+                "parentHash": "0x98eff2712c5546167a22d9d3ab340005d8f736d49e8867ab2e67400526dc5d2c",
+                "feeRecipient": "0xe7cf7c3ba875dd3884ed6a9082d342cb4fbb1f1b",
+                "stateRoot": "0x54874eaadc381f61c2999a93c59c36e564a42062d64955e057991534fc166504",
+                "receiptsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "logsBloom": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+                "prevRandao": "0x883fbdbbc4a4c75747422bc271c43bf6370f570c43cccd81f80cae71f54ad3da",
+                "blockNumber": "0x21b0",
+                "gasLimit": "0x1c9c380",
+                "gasUsed": "0x0",
+                "timestamp": "0x63d2af38",
+                "extraData": "0xd883010b00846765746888676f312e31392e35856c696e7578",
+                "baseFeePerGas": "0x7",
+                "blockHash": "0x1587569314611d9f06aac37c64c87b180313056d1a968e6b8290ce64c519859f",
+                "transactions": [
+                    "0xf86e078459682f0782520894419f2d6c3f5fe8bf43f91923ba21e996032897298894a1739b5e1d49c8808328d2f0a069dffffc6f9b20157bd17872d326de8ed088de3e24f2801dd9375ddbecd013f0a041aab6f5dff83fdd2595cc55725b28128b8902f12f3db598dce9f9183f989300",
+                    "0x02f87883146966830516988459682f008459682f078252089432960b83199ae0f78756dbcf016a8e88e4dd7a748894a19041886f000080c001a0f916421115b1dc667b959fe32fa01cc9ba07942078b9e28435fd0a55c1cbf2dba076da1b6e79fa9a3b6b77e1601546fa194652a3f9a73919c470254833dfae68f8",
+                    "0x02f87883146966830516998459682f008459682f0782520894b467d5ec9f6db8b1c156d40e65ebf88b2596ab198894a19041886f000080c001a0cc9ddcece6913c48e3aaaab25fb4f98da8540f1ffac58b010c9d3d0c60e01edba073cbf451658aa60dac89b62a463a2a95cdba3c73e5d258f80eedd6d465ab0772",
+                    "0x02f878831469668305169a8459682f008459682f078252089477831a3a5552ad92848d7134a1e467c1089fb04a8894a19041886f000080c001a0f3687841790c73693a44710dfc83d02f7044ea821cca5a66b92b283c2c346d62a013695d07f9e62132a8c3c423dc4b74b914b5fa758f88fcf2ed10242aaa68ca6a",
+                ],
+                "withdrawals": [
+                    {
+                        "index": "0x18561",
+                        "validatorIndex": "0x7c2e8",
+                        "address": "0xf97e180c050e5ab072211ad2c213eb5aee4df134",
+                        "amount": "0x18111",
+                    },
+                    {
+                        "index": "0x18562",
+                        "validatorIndex": "0x7c2e9",
+                        "address": "0xf97e180c050e5ab072211ad2c213eb5aee4df134",
+                        "amount": "0x583a6",
+                    },
+                ],
+            },
+            "blockValue": block_value,
+        },
+    })
+}
+
+pub fn default_eth1_api() -> Eth1Api {
+    eth1_api_with_urls(vec![])
+}
+
+pub fn eth1_api_with_urls(urls: Vec<Url>) -> Eth1Api {
+    Eth1Api::new(
+        Arc::new(Config::mainnet()),
+        Client::new(),
+        Arc::default(),
+        urls,
+        None,
+        None,
+    )
+}
+
+pub fn eth1_block_json(
+    number: ExecutionBlockNumber,
+    hash: ExecutionBlockHash,
+    parent_hash: ExecutionBlockHash,
+) -> Value {
+    json!({
+        "hash": hash,
+        "parentHash": parent_hash,
+        "sha3Uncles": H256::zero(),
+        "miner": ExecutionAddress::zero(),
+        "stateRoot": H256::zero(),
+        "transactionsRoot": H256::zero(),
+        "receiptsRoot": H256::zero(),
+        "number": U64::from(number),
+        "gasUsed": "0x0",
+        "gasLimit": "0x1c9c380",
+        "extraData": "0x",
+        "logsBloom": null,
+        "timestamp": "0x63d2af38",
+        "difficulty": "0x0",
+        "totalDifficulty": "0x1",
+        "uncles": [],
+        "transactions": [],
+    })
+}
+
+pub fn deposit_log_json(block_number: &str, index: DepositIndex) -> Value {
+    // A `DepositEvent` log takes up 18 EVM words. The index is in the 18th one.
+    let mut data = [0; 18 * 32];
+    data[17 * 32..17 * 32 + 8].copy_from_slice(&index.to_le_bytes());
+
+    json!({
+        "address": Config::mainnet().deposit_contract_address,
+        "topics": [DepositEvent::TOPIC],
+        "data": format!("0x{}", hex::encode(data)),
+        "blockHash": H256::repeat_byte(1),
+        "blockNumber": block_number,
+        "transactionHash": H256::repeat_byte(3),
+        "transactionIndex": "0x0",
+        "logIndex": "0x0",
+        "removed": false,
+    })
+}
+
+pub fn receipt_json(block_number: &str, logs: Vec<Value>) -> Value {
+    json!({
+        "transactionHash": H256::repeat_byte(3),
+        "transactionIndex": "0x0",
+        "blockHash": H256::repeat_byte(1),
+        "blockNumber": block_number,
+        "from": ExecutionAddress::repeat_byte(2),
+        "to": Config::mainnet().deposit_contract_address,
+        "cumulativeGasUsed": "0xd4c8",
+        "gasUsed": "0xd4c8",
+        "contractAddress": null,
+        "logs": logs,
+        "logsBloom": format!("0x{}", "00".repeat(256)),
+        "status": "0x1",
+        "type": "0x2",
+        "effectiveGasPrice": "0x3b9aca00",
+    })
+}
+
+pub fn default_payload_attributes<P: Preset>() -> PayloadAttributes<P> {
+    PayloadAttributes::Bellatrix(PayloadAttributesV1 {
+        timestamp: 1,
+        prev_randao: H256::zero(),
+        suggested_fee_recipient: ExecutionAddress::zero(),
+    })
+}
+
+pub fn default_payload<P: Preset>() -> ExecutionPayload<P> {
+    BellatrixExecutionPayload::default().into()
+}
@@ -0,0 +1,2050 @@
+use core::{
+    hash::{Hash as _, Hasher as _},
+    sync::atomic::Ordering,
+    time::Duration,
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    hash::DefaultHasher,
+    io::Write as _,
+    sync::Mutex as StdMutex,
+    time::{Instant, SystemTime},
+};
+
+use anyhow::{bail, ensure, Error as AnyhowError, Result};
+use flate2::{write::GzEncoder, Compression};
+use futures::{channel::oneshot, Future};
+use log::{debug, warn};
+use reqwest::{
+    header::{HeaderMap, CONTENT_ENCODING, CONTENT_TYPE, RETRY_AFTER},
+    StatusCode, Url,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tokio::time;
+use web3::{
+    api::{Eth, Namespace as _},
+    error::TransportError,
+    helpers::CallFuture,
+    rpc::{Error as RpcError, ErrorCode},
+    transports::Http,
+    Error as Web3Error, Transport as _, Web3,
+};
+
+use crate::{
+    eth1_api::{
+        api::{Error, Eth1Api},
+        engine::{EngineCall, EngineCallRecord},
+    },
+    Eth1ApiToMetrics, Eth1ConnectionData,
+};
+
+// Providers that are still rate limiting after this many retries are treated as failing.
+const MAX_RATE_LIMIT_RETRIES: usize = 3;
+
+// Waiting longer than a slot for a single request is worse than trying another endpoint.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(12);
+
+// Enough to recognize an error page or a truncated response without flooding logs with payloads.
+const MAX_RESPONSE_BODY_IN_ERROR: usize = 300;
+
+// `web3` prefixes errors for responses it cannot parse with this.
+const WEB3_DESERIALIZATION_ERROR_PREFIX: &str = "failed to deserialize response: ";
+
+impl Eth1Api {
+    pub(crate) async fn execute<T: DeserializeOwned + Send>(
+        &self,
+        method: &str,
+        params: Vec<Value>,
+        timeout: Option<Duration>,
+    ) -> Result<T> {
+        if self.coalesce_engine_calls && method.starts_with("engine_") {
+            let raw = self.execute_coalesced(method, params, timeout).await?;
+            return deserialize_response(method, raw);
+        }
+
+        self.execute_counting_endpoints(
+            None,
+            method,
+            params,
+            timeout,
+            &mut RequestReport::default(),
+        )
+        .await
+    }
+
+    pub(crate) async fn execute_on<T: DeserializeOwned + Send>(
+        &self,
+        endpoint: Option<&Url>,
+        method: &str,
+        params: Vec<Value>,
+        timeout: Duration,
+    ) -> Result<T> {
+        match endpoint {
+            Some(_) => {
+                self.execute_counting_endpoints(
+                    endpoint,
+                    method,
+                    params,
+                    Some(timeout),
+                    &mut RequestReport::default(),
+                )
+                .await
+            }
+            None => self.execute(method, params, Some(timeout)).await,
+        }
+    }
+
+    async fn execute_coalesced(
+        &self,
+        method: &str,
+        params: Vec<Value>,
+        timeout: Option<Duration>,
+    ) -> Result<Value> {
+        let key = (method.to_owned(), hash_params(&params));
+
+        let role = {
+            let mut in_flight_engine_calls = self
+                .in_flight_engine_calls
+                .lock()
+                .expect("in-flight engine call mutex is poisoned");
+
+            match in_flight_engine_calls.get_mut(&key) {
+                Some(call) if call.params == params => {
+                    let (sender, receiver) = oneshot::channel();
+                    call.waiters.push(sender);
+                    CoalescedCallRole::Waiter(receiver)
+                }
+                // Parameters with colliding hashes are sent separately.
+                Some(_) => CoalescedCallRole::Independent,
+                None => {
+                    in_flight_engine_calls.insert(
+                        key.clone(),
+                        InFlightCall {
+                            params: params.clone(),
+                            waiters: vec![],
+                        },
+                    );
+
+                    CoalescedCallRole::Leader
+                }
+            }
+        };
+
+        let is_leader = match role {
+            CoalescedCallRole::Leader => true,
+            CoalescedCallRole::Waiter(receiver) => {
+                // The sender is dropped if the shared request fails or its caller is cancelled.
+                if let Ok(raw) = receiver.await {
+                    return Ok(raw);
+                }
+
+                false
+            }
+            CoalescedCallRole::Independent => false,
+        };
+
+        if !is_leader {
+            return self
+                .execute_counting_endpoints(
+                    None,
+                    method,
+                    params,
+                    timeout,
+                    &mut RequestReport::default(),
+                )
+                .await;
+        }
+
+        let mut guard = InFlightCallGuard {
+            calls: &self.in_flight_engine_calls,
+            key: Some(key),
+        };
+
+        let raw: Value = self
+            .execute_counting_endpoints(
+                None,
+                method,
+                params,
+                timeout,
+                &mut RequestReport::default(),
+            )
+            .await?;
+
+        if let Some(call) = guard.finish() {
+            for waiter in call.waiters {
+                // The waiter may have been cancelled. That is not an error.
+                waiter.send(raw.clone()).ok();
+            }
+        }
+
+        Ok(raw)
+    }
+
+    // Used for calls made while proposing a block.
+    // The report is logged so that missed slots can be traced back to slow or failing endpoints.
+    //
+    // Passing `Some(endpoint)` bypasses fallback logic and sends the request to `endpoint` only.
+    // This is needed for requests that only make sense for a specific execution client,
+    // such as `engine_getPayload` with a payload ID issued by that client.
+    pub(crate) async fn execute_with_report<T: DeserializeOwned + Send>(
+        &self,
+        endpoint: Option<&Url>,
+        method: &str,
+        params: Vec<Value>,
+        timeout: Option<Duration>,
+    ) -> (Result<T>, RequestReport) {
+        let start = Instant::now();
+        let mut report = RequestReport::default();
+
+        let result = self
+            .execute_counting_endpoints(endpoint, method, params, timeout, &mut report)
+            .await;
+
+        report.elapsed = start.elapsed();
+
+        debug!(
+            "{method} {} in {:?} (endpoints tried: {}, retries: {})",
+            if result.is_ok() {
+                "succeeded"
+            } else {
+                "failed"
+            },
+            report.elapsed,
+            report.endpoints_tried,
+            report.retries(),
+        );
+
+        (result, report)
+    }
+
+    async fn execute_counting_endpoints<T: DeserializeOwned + Send>(
+        &self,
+        endpoint: Option<&Url>,
+        method: &str,
+        params: Vec<Value>,
+        timeout: Option<Duration>,
+        report: &mut RequestReport,
+    ) -> Result<T> {
+        let is_engine_call = method.starts_with("engine_");
+        let observer = self
+            .engine_call_observer
+            .as_ref()
+            .filter(|_| is_engine_call);
+        let record = is_engine_call && self.recent_engine_call_capacity > 0;
+
+        if observer.is_none() && !record {
+            return self
+                .execute_unobserved(endpoint, method, params, timeout, report)
+                .await;
+        }
+
+        // Parameters may be large, so hash them only if an observer needs the hash.
+        let params_hash = observer.map(|_| hash_params(&params));
+        let timestamp = SystemTime::now();
+        let start = Instant::now();
+
+        let result = self
+            .execute_unobserved(endpoint, method, params, timeout, report)
+            .await;
+
+        let elapsed = start.elapsed();
+
+        if let Some((observer, params_hash)) = observer.zip(params_hash) {
+            observer.observe(&EngineCall {
+                method,
+                params_hash,
+                succeeded: result.is_ok(),
+                elapsed,
+                endpoints_tried: report.endpoints_tried,
+            });
+        }
+
+        if record {
+            let mut recent_engine_calls = self.recent_engine_calls.lock().await;
+
+            if recent_engine_calls.len() == self.recent_engine_call_capacity {
+                recent_engine_calls.pop_front();
+            }
+
+            recent_engine_calls.push_back(EngineCallRecord {
+                method: method.to_owned(),
+                error: result.as_ref().err().map(|error| format!("{error:#}")),
+                elapsed,
+                timestamp,
+            });
+        }
+
+        result
+    }
+
+    async fn execute_unobserved<T: DeserializeOwned + Send>(
+        &self,
+        endpoint: Option<&Url>,
+        method: &str,
+        params: Vec<Value>,
+        timeout: Option<Duration>,
+        report: &mut RequestReport,
+    ) -> Result<T> {
+        let _timer = self.metrics.as_ref().map(|metrics| {
+            prometheus_metrics::start_timer_vec(&metrics.eth1_api_request_times, method)
+        });
+
+        let result = self
+            .request_counting_endpoints(
+                endpoint,
+                |(url, api, headers)| {
+                    Ok(CallFuture::new(self.send_request(
+                        api.transport(),
+                        url,
+                        method,
+                        params.clone(),
+                        headers,
+                        timeout,
+                    )))
+                },
+                report,
+            )
+            .await;
+
+        let raw: Value = result.map_err(|error| explain_missing_result(method, error))?;
+
+        // Lets operators confirm which method versions are used with each execution client.
+        // Fallbacks to other versions (e.g., `engine_newPayloadV2` instead of V1) count as well.
+        if let (Some(metrics), Some((name, version))) =
+            (self.metrics.as_ref(), split_method_version(method))
+        {
+            metrics
+                .eth1_api_engine_method_versions
+                .with_label_values(&[name, version])
+                .inc();
+        }
+
+        deserialize_response(method, raw)
+    }
+
+    pub(crate) async fn request_with_fallback<R, O, F>(&self, request_from_api: R) -> Result<O>
+    where
+        R: Fn((Eth<Http>, Option<HeaderMap>)) -> Result<CallFuture<O, F>> + Sync + Send,
+        O: DeserializeOwned + Send,
+        F: Future<Output = Result<Value, Web3Error>> + Send,
+    {
+        self.request_with_fallback_counting_endpoints(
+            |(_, api, headers)| request_from_api((api, headers)),
+            &mut RequestReport::default(),
+        )
+        .await
+    }
+
+    pub(crate) async fn request_with_fallback_counting_endpoints<R, O, F>(
+        &self,
+        request_from_api: R,
+        report: &mut RequestReport,
+    ) -> Result<O>
+    where
+        R: Fn((&Url, Eth<Http>, Option<HeaderMap>)) -> Result<CallFuture<O, F>> + Sync + Send,
+        O: DeserializeOwned + Send,
+        F: Future<Output = Result<Value, Web3Error>> + Send,
+    {
+        self.request_counting_endpoints(None, request_from_api, report)
+            .await
+    }
+
+    // Passing `Some(endpoint)` bypasses fallback logic and sends the request to `endpoint` only.
+    // The outcome is recorded the same way as with fallback.
+    async fn request_counting_endpoints<R, O, F>(
+        &self,
+        endpoint: Option<&Url>,
+        request_from_api: R,
+        report: &mut RequestReport,
+    ) -> Result<O>
+    where
+        R: Fn((&Url, Eth<Http>, Option<HeaderMap>)) -> Result<CallFuture<O, F>> + Sync + Send,
+        O: DeserializeOwned + Send,
+        F: Future<Output = Result<Value, Web3Error>> + Send,
+    {
+        ensure!(!self.sync_only, Error::Eth1Disabled);
+
+        if let Some(url) = endpoint {
+            report.endpoints_tried = 1;
+
+            let result = self.request_endpoint(url, &request_from_api).await?;
+
+            self.record_endpoints_tried(report.endpoints_tried);
+
+            match &result {
+                Ok(_) => report.served_by = Some(url.clone()),
+                Err(_) => self.send_to_metrics(Eth1ApiToMetrics::Eth1Connection(
+                    Eth1ConnectionData::default(),
+                )),
+            }
+
+            return result.map_err(classify_web3_error);
+        }
+
+        let mut exhaustion_grace = self.exhaustion_grace;
+        // Attached to `Error::EndpointsExhausted` so that callers can tell why endpoints failed.
+        let mut last_error = None;
+
+        loop {
+            while let Some(url) = self.current_endpoint().await {
+                report.endpoints_tried += 1;
+
+                match self.request_endpoint(&url, &request_from_api).await? {
+                    Ok(result) => {
+                        self.record_endpoints_tried(report.endpoints_tried);
+                        self.record_sticky_endpoint(&url);
+
+                        report.served_by = Some(url);
+
+                        return Ok(result);
+                    }
+                    Err(error) => {
+                        // The endpoint is working but cannot return that many results.
+                        // Let the caller decide whether to make smaller requests instead.
+                        // Endpoints that do not support a method are failed over as usual because
+                        // other endpoints in a mixed set may support it.
+                        if is_web3_result_cap_exceeded(&error) {
+                            self.record_endpoints_tried(report.endpoints_tried);
+                            return Err(error.into());
+                        }
+
+                        let error = classify_web3_error(error);
+
+                        self.clear_sticky_endpoint(&url);
+
+                        match self.peek_next_endpoint(&url).await {
+                            Some(next_eth) => {
+                                let switches = self.record_endpoint_switch(&url, &next_eth);
+
+                                warn!(
+                                    "Eth1 RPC endpoint {url} returned an error: {error}; \
+                                     switching to {next_eth} ({switches} switches so far)",
+                                );
+                            }
+                            None => warn!(
+                                "last available Eth1 RPC endpoint {url} returned an error: {error}",
+                            ),
+                        }
+
+                        self.send_to_metrics(Eth1ApiToMetrics::Eth1Connection(
+                            Eth1ConnectionData::default(),
+                        ));
+
+                        self.next_endpoint(&url).await;
+
+                        last_error = Some(error);
+                    }
+                }
+            }
+
+            self.reset_endpoints().await;
+
+            if let Some(metrics) = self.metrics.as_ref() {
+                metrics.eth1_api_reset_count.inc();
+            }
+
+            // Give endpoints that are briefly unavailable (e.g. restarting) one more chance.
+            let Some(grace) = exhaustion_grace
+                .take()
+                .filter(|_| !self.original.is_empty())
+            else {
+                break;
+            };
+
+            warn!("all Eth1 RPC endpoints failed; trying them again in {grace:?}");
+
+            time::sleep(grace).await;
+        }
+
+        self.record_endpoints_tried(report.endpoints_tried);
+
+        // Checking this in `Eth1Api::new` would be unnecessarily strict.
+        // Syncing a predefined network without proposing blocks does not require an Eth1 RPC
+        // (except during the Merge transition).
+        ensure!(!self.original.is_empty(), Error::NoEndpointsProvided);
+
+        match last_error {
+            Some(error) => Err(error.context(Error::EndpointsExhausted)),
+            None => bail!(Error::EndpointsExhausted),
+        }
+    }
+
+    // Sends a request to `url`, retrying it if the endpoint is rate limiting requests.
+    // Records the outcome for metrics and endpoint health but does not switch endpoints.
+    // A concurrency permit is only held while a request is in flight so that requests waiting
+    // out rate limits or `exhaustion_grace` do not keep other requests from being sent.
+    async fn request_endpoint<R, O, F>(
+        &self,
+        url: &Url,
+        request_from_api: &R,
+    ) -> Result<Result<O, Web3Error>>
+    where
+        R: Fn((&Url, Eth<Http>, Option<HeaderMap>)) -> Result<CallFuture<O, F>> + Sync + Send,
+        O: DeserializeOwned + Send,
+        F: Future<Output = Result<Value, Web3Error>> + Send,
+    {
+        let http = Http::with_client(self.client.clone(), url.clone());
+        let api = Web3::new(http).eth();
+        let mut rate_limit_retries = 0;
+        let start = Instant::now();
+
+        let query = loop {
+            let permit = self.request_permits.acquire().await?;
+            let headers = self.auth.headers()?;
+            let query = request_from_api((url, api.clone(), headers))?.await;
+
+            drop(permit);
+
+            let backoff = match &query {
+                Err(error) if is_rate_limited(error) => {
+                    // Take the hint even if it goes unused so that it does not apply to
+                    // later responses without a `Retry-After` header.
+                    self.take_retry_after_hint(url)
+                        .or(self.rate_limit_backoff)
+                        .filter(|_| rate_limit_retries < MAX_RATE_LIMIT_RETRIES)
+                }
+                _ => None,
+            };
+
+            let Some(backoff) = backoff else {
+                break query;
+            };
+
+            rate_limit_retries += 1;
+
+            warn!(
+                "Eth1 RPC endpoint {url} is rate limiting requests; \
+                 retrying in {backoff:?} \
+                 (retry {rate_limit_retries} of {MAX_RATE_LIMIT_RETRIES})",
+            );
+
+            time::sleep(backoff).await;
+        };
+
+        match &query {
+            Ok(_) => {
+                self.send_to_metrics(Eth1ApiToMetrics::Eth1Connection(Eth1ConnectionData {
+                    sync_eth1_connected: true,
+                    sync_eth1_fallback_connected: self.original.first() != Some(url),
+                }));
+
+                self.record_latency(url, start.elapsed()).await;
+            }
+            Err(error) => {
+                if let Some(metrics) = self.metrics.as_ref() {
+                    metrics.eth1_api_errors_count.inc();
+                }
+
+                self.record_error(url, error).await;
+            }
+        }
+
+        Ok(query)
+    }
+
+    fn take_retry_after_hint(&self, url: &Url) -> Option<Duration> {
+        self.retry_after_hints
+            .lock()
+            .expect("Retry-After hint mutex is poisoned")
+            .remove(url)
+    }
+
+    pub(crate) fn send_request<'request>(
+        &'request self,
+        http: &Http,
+        url: &Url,
+        method: &'request str,
+        params: Vec<Value>,
+        headers: Option<HeaderMap>,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<Value, Web3Error>> + Send + 'request {
+        let http = http.clone();
+        let url = url.clone();
+
+        async move {
+            // Other requests are too small to be worth compressing.
+            if method.starts_with("engine_") && self.compresses_requests_to(&url) {
+                match self
+                    .execute_compressed(&url, method, &params, headers.clone(), timeout)
+                    .await
+                {
+                    CompressedResponse::Completed(result) => return result,
+                    CompressedResponse::Rejected { reason } => {
+                        warn!(
+                            "Eth1 RPC endpoint {url} rejected a compressed request ({reason}); \
+                             sending it uncompressed requests from now on",
+                        );
+
+                        self.uncompressed_endpoints
+                            .lock()
+                            .expect("uncompressed endpoint mutex is poisoned")
+                            .insert(url.clone());
+                    }
+                }
+            }
+
+            http.execute_with_headers(method, params, headers, timeout)
+                .await
+        }
+    }
+
+    fn compresses_requests_to(&self, url: &Url) -> bool {
+        self.compress_engine_requests
+            && !self
+                .uncompressed_endpoints
+                .lock()
+                .expect("uncompressed endpoint mutex is poisoned")
+                .contains(url)
+    }
+
+    // `web3::transports::Http` has no way to compress request bodies.
+    async fn execute_compressed(
+        &self,
+        url: &Url,
+        method: &str,
+        params: &[Value],
+        headers: Option<HeaderMap>,
+        timeout: Option<Duration>,
+    ) -> CompressedResponse {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1,
+        });
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+
+        if let Err(error) = encoder.write_all(request.to_string().as_bytes()) {
+            return CompressedResponse::Completed(Err(transport_error(error)));
+        }
+
+        let body = match encoder.finish() {
+            Ok(body) => body,
+            Err(error) => return CompressedResponse::Completed(Err(transport_error(error))),
+        };
+
+        let mut builder = self
+            .client
+            .post(url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_ENCODING, "gzip")
+            .body(body);
+
+        if let Some(headers) = headers {
+            builder = builder.headers(headers);
+        }
+
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        let response = match builder.send().await {
+            Ok(response) => response,
+            Err(error) => return CompressedResponse::Completed(Err(transport_error(error))),
+        };
+
+        let status = response.status();
+
+        if status == StatusCode::UNSUPPORTED_MEDIA_TYPE {
+            return CompressedResponse::Rejected {
+                reason: status.to_string(),
+            };
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            if let Some(delay) = retry_after(response.headers()) {
+                self.retry_after_hints
+                    .lock()
+                    .expect("Retry-After hint mutex is poisoned")
+                    .insert(url.clone(), delay.min(MAX_RETRY_AFTER));
+            }
+        }
+
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(error) => return CompressedResponse::Completed(Err(transport_error(error))),
+        };
+
+        // Some execution clients report errors with a status other than `200 OK`.
+        let Ok(mut response) = serde_json::from_slice::<Value>(&bytes) else {
+            let body = String::from_utf8_lossy(&bytes);
+
+            if status.is_client_error() && mentions_content_encoding(&body) {
+                return CompressedResponse::Rejected {
+                    reason: format!("{status}: {body}"),
+                };
+            }
+
+            if !status.is_success() {
+                return CompressedResponse::Completed(Err(Web3Error::Transport(
+                    TransportError::Code(status.as_u16()),
+                )));
+            }
+
+            return CompressedResponse::Completed(Err(Web3Error::InvalidResponse(
+                body.into_owned(),
+            )));
+        };
+
+        if let Some(error) = response.get_mut("error").map(Value::take) {
+            let result = serde_json::from_value::<RpcError>(error)
+                .map_err(|error| Web3Error::InvalidResponse(error.to_string()));
+
+            return match result {
+                Ok(error) if mentions_content_encoding(&error.message) => {
+                    CompressedResponse::Rejected {
+                        reason: error.message,
+                    }
+                }
+                Ok(error) => CompressedResponse::Completed(Err(Web3Error::Rpc(error))),
+                Err(error) => CompressedResponse::Completed(Err(error)),
+            };
+        }
+
+        if !status.is_success() {
+            return CompressedResponse::Completed(Err(Web3Error::Transport(TransportError::Code(
+                status.as_u16(),
+            ))));
+        }
+
+        if response.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+            return CompressedResponse::Completed(Err(Web3Error::InvalidResponse(
+                response.to_string(),
+            )));
+        }
+
+        CompressedResponse::Completed(
+            response
+                .get_mut("result")
+                .map(Value::take)
+                .ok_or_else(|| Web3Error::InvalidResponse(response.to_string())),
+        )
+    }
+
+    // An `endpoints_tried` value consistently above 1 means the primary endpoint is unhealthy.
+    fn record_endpoints_tried(&self, endpoints_tried: usize) {
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.set_eth1_api_endpoints_tried_last(endpoints_tried);
+        }
+    }
+
+    fn send_to_metrics(&self, message: Eth1ApiToMetrics) {
+        let Some(metrics_tx) = self.eth1_api_to_metrics_tx.as_ref() else {
+            return;
+        };
+
+        if self.metrics_channel_closed.load(Ordering::Relaxed) {
+            return;
+        }
+
+        if metrics_tx.unbounded_send(message).is_err()
+            && !self.metrics_channel_closed.swap(true, Ordering::Relaxed)
+        {
+            debug!(
+                "send to metrics service failed because the receiver was dropped; \
+                 no more Eth1 connection data will be sent",
+            );
+        }
+    }
+}
+
+// `web3` fails to parse bodies that are not JSON-RPC 2.0 responses with errors that look like
+// problems with the contents. Proxies that respond with HTML error pages make this common enough
+// that it is worth a dedicated error.
+//
+// `Web3Error::Decoder` errors are left alone. They mean that the response was valid JSON-RPC 2.0
+// but its result did not have the expected shape.
+fn classify_web3_error(error: Web3Error) -> AnyhowError {
+    match unparsed_response_body(&error) {
+        Some(body) if json_rpc_response(body).is_none() => Error::NonJsonResponse {
+            message: body.to_owned(),
+        }
+        .into(),
+        _ => error.into(),
+    }
+}
+
+// Returns the body of a response that could not be parsed as a JSON-RPC 2.0 response.
+// `web3` includes it in transport errors after the error from `serde_json`.
+// `Eth1Api::execute_compressed` returns it in `Web3Error::InvalidResponse`.
+fn unparsed_response_body(error: &Web3Error) -> Option<&str> {
+    match error {
+        Web3Error::InvalidResponse(body) => Some(body),
+        Web3Error::Transport(TransportError::Message(message)) => {
+            let details = message.strip_prefix(WEB3_DESERIALIZATION_ERROR_PREFIX)?;
+
+            // Errors from `serde_json` may contain the separator as well.
+            let mut bodies = details
+                .match_indices(": ")
+                .filter_map(|(index, separator)| details.get(index + separator.len()..));
+
+            let json_body = bodies
+                .clone()
+                .find(|body| serde_json::from_str::<Value>(body).is_ok());
+
+            json_body.or_else(|| bodies.next()).or(Some(details))
+        }
+        _ => None,
+    }
+}
+
+fn json_rpc_response(body: &str) -> Option<Value> {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .filter(|raw| raw.get("jsonrpc").and_then(Value::as_str) == Some("2.0"))
+}
+
+// Splits Engine API method names like `engine_newPayloadV3` into `engine_newPayload` and `V3`.
+fn split_method_version(method: &str) -> Option<(&str, &str)> {
+    if !method.starts_with("engine_") {
+        return None;
+    }
+
+    let (name, digits) = method.rsplit_once('V')?;
+    let version = method.strip_prefix(name)?;
+
+    (!digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()))
+        .then_some((name, version))
+}
+
+fn hash_params(params: &[Value]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    Value::Array(params.to_vec()).to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+// Deserialize separately to keep the raw response around for diagnosis.
+// Errors from `serde` alone do not say which method the response was for.
+fn deserialize_response<T: DeserializeOwned>(method: &str, raw: Value) -> Result<T> {
+    T::deserialize(&raw).map_err(|error| {
+        Error::MalformedResponse {
+            method: method.to_owned(),
+            raw,
+            error,
+        }
+        .into()
+    })
+}
+
+// JSON-RPC 2.0 responses with neither `result` nor `error` are reported as
+// deserialization errors with the response in the message. Say what is wrong with them instead.
+fn explain_missing_result(method: &str, error: AnyhowError) -> AnyhowError {
+    let raw = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref().and_then(unparsed_response_body))
+        .and_then(json_rpc_response)
+        .filter(|raw| raw.get("result").is_none());
+
+    match raw {
+        Some(raw) => error.context(Error::MalformedResponse {
+            method: method.to_owned(),
+            raw,
+            error: serde::de::Error::missing_field("result"),
+        }),
+        None => error,
+    }
+}
+
+// Responses may be entire error pages or execution payloads.
+pub(crate) fn truncate_body(body: &str) -> Cow<str> {
+    if body.len() <= MAX_RESPONSE_BODY_IN_ERROR {
+        return Cow::Borrowed(body);
+    }
+
+    let prefix = (0..=MAX_RESPONSE_BODY_IN_ERROR)
+        .rev()
+        .find_map(|end| body.get(..end))
+        .unwrap_or_default();
+
+    Cow::Owned(format!("{prefix}... ({} bytes in total)", body.len()))
+}
+
+// Servers that do not support compressed bodies tend to say so in their error messages.
+// Parse errors alone are not treated as rejections. They may be caused by anything.
+fn mentions_content_encoding(message: &str) -> bool {
+    message.to_ascii_lowercase().contains("content-encoding")
+}
+
+// Only the delay-seconds form is supported. Providers do not send HTTP dates in practice.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+fn is_rate_limited(error: &Web3Error) -> bool {
+    matches!(
+        error,
+        Web3Error::Transport(TransportError::Code(code))
+            if *code == StatusCode::TOO_MANY_REQUESTS.as_u16()
+    )
+}
+
+// Timeouts are reported as transport errors as well.
+// Responses that `web3` could not parse are too, but they would only be repeated.
+pub(crate) fn is_transport_error(error: &AnyhowError) -> bool {
+    matches!(
+        error.downcast_ref(),
+        Some(error @ Web3Error::Transport(_)) if unparsed_response_body(error).is_none(),
+    )
+}
+
+pub(crate) fn is_method_not_found(error: &AnyhowError) -> bool {
+    error.downcast_ref().is_some_and(is_web3_method_not_found)
+}
+
+fn is_web3_method_not_found(error: &Web3Error) -> bool {
+    matches!(error, Web3Error::Rpc(rpc_error) if rpc_error.code == ErrorCode::MethodNotFound)
+}
+
+pub(crate) fn is_result_cap_exceeded(error: &AnyhowError) -> bool {
+    error
+        .downcast_ref()
+        .is_some_and(is_web3_result_cap_exceeded)
+}
+
+// Messages returned by Geth (and providers based on it) and Alchemy respectively.
+fn is_web3_result_cap_exceeded(error: &Web3Error) -> bool {
+    matches!(
+        error,
+        Web3Error::Rpc(rpc_error)
+            if rpc_error.message.contains("query returned more than")
+                || rpc_error.message.contains("Log response size exceeded")
+    )
+}
+
+fn transport_error(error: impl core::fmt::Display) -> Web3Error {
+    Web3Error::Transport(TransportError::Message(error.to_string()))
+}
+
+pub(crate) type InFlightCallKey = (String, u64);
+
+enum CompressedResponse {
+    Completed(Result<Value, Web3Error>),
+    // The request was not processed and can be sent again uncompressed.
+    Rejected { reason: String },
+}
+
+enum CoalescedCallRole {
+    // Sends the request and shares the response.
+    Leader,
+    // Waits for the response to an identical request.
+    Waiter(oneshot::Receiver<Value>),
+    // Sends the request without sharing the response.
+    Independent,
+}
+
+pub(crate) struct InFlightCall {
+    params: Vec<Value>,
+    waiters: Vec<oneshot::Sender<Value>>,
+}
+
+// Removes the entry for a coalesced call even if the future making it is dropped.
+// Otherwise later identical calls would wait for a response that never arrives.
+struct InFlightCallGuard<'calls> {
+    calls: &'calls StdMutex<HashMap<InFlightCallKey, InFlightCall>>,
+    key: Option<InFlightCallKey>,
+}
+
+impl InFlightCallGuard<'_> {
+    fn finish(&mut self) -> Option<InFlightCall> {
+        let key = self.key.take()?;
+        self.calls.lock().ok()?.remove(&key)
+    }
+}
+
+impl Drop for InFlightCallGuard<'_> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub(crate) struct RequestReport {
+    elapsed: Duration,
+    pub(crate) endpoints_tried: usize,
+    // The endpoint that returned a response, if any.
+    pub(crate) served_by: Option<Url>,
+}
+
+impl RequestReport {
+    const fn retries(&self) -> usize {
+        self.endpoints_tried.saturating_sub(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    use ethereum_types::H64;
+    use execution_engine::{PayloadId, PayloadValidationStatus};
+    use flate2::read::GzDecoder;
+    use futures::{channel::mpsc, future};
+    use httpmock::{Method, MockServer};
+    use prometheus_metrics::Metrics;
+    use reqwest::Client;
+    use std_ext::ArcExt as _;
+    use test_case::test_case;
+    use tokio::{
+        io::copy_bidirectional,
+        net::{TcpListener, TcpStream},
+    };
+    use types::{
+        capella::containers::ExecutionPayload as CapellaExecutionPayload, config::Config,
+        phase0::primitives::H256, preset::Mainnet,
+    };
+    use web3::types::U64;
+
+    use crate::eth1_api::{
+        engine::ENGINE_GET_PAYLOAD_TIMEOUT,
+        helpers::{default_eth1_api, default_payload, eth1_api_with_urls, eth1_block_json},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_engine_requests_are_compressed_when_enabled() -> Result<()> {
+        let server = MockServer::start();
+
+        let compressed_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .header("content-encoding", "gzip")
+                .matches(|request| {
+                    let Some(body) = request.body.as_deref() else {
+                        return false;
+                    };
+
+                    let mut decompressed = String::new();
+
+                    if std::io::Read::read_to_string(&mut GzDecoder::new(body), &mut decompressed)
+                        .is_err()
+                    {
+                        return false;
+                    }
+
+                    serde_json::from_str::<Value>(&decompressed)
+                        .is_ok_and(|request| request["method"] == "engine_newPayloadV1")
+                });
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": null,
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api =
+            eth1_api_with_urls(vec![server.url("/").parse()?]).with_engine_request_compression();
+
+        let payload_status = eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await?;
+
+        assert_eq!(payload_status.status, PayloadValidationStatus::Valid);
+
+        compressed_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_engine_request_compression_is_disabled_after_rejection() -> Result<()> {
+        let server = MockServer::start();
+
+        let compressed_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .header("content-encoding", "gzip");
+
+            then.status(415);
+        });
+
+        let uncompressed_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .header_missing("content-encoding")
+                .body_contains("engine_newPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": null,
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api =
+            eth1_api_with_urls(vec![server.url("/").parse()?]).with_engine_request_compression();
+
+        for _ in 0..2 {
+            let payload_status = eth1_api
+                .new_payload::<Mainnet>(default_payload(), None)
+                .await?;
+
+            assert_eq!(payload_status.status, PayloadValidationStatus::Valid);
+        }
+
+        compressed_mock.assert_hits(1);
+        uncompressed_mock.assert_hits(2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_engine_request_compression_is_disabled_per_endpoint() -> Result<()> {
+        let rejecting_server = MockServer::start();
+        let accepting_server = MockServer::start();
+
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "result": {
+                "status": "VALID",
+                "latestValidHash": null,
+                "validationError": null,
+            },
+        });
+
+        let rejected_mock = rejecting_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .header("content-encoding", "gzip");
+
+            then.status(400).body("unsupported Content-Encoding: gzip");
+        });
+
+        let uncompressed_mock = rejecting_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .header_missing("content-encoding");
+
+            then.status(200).body(body.to_string());
+        });
+
+        let compressed_mock = accepting_server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .header("content-encoding", "gzip");
+
+            then.status(200).body(body.to_string());
+        });
+
+        let accepting_url = accepting_server.url("/").parse()?;
+
+        let eth1_api = eth1_api_with_urls(vec![
+            rejecting_server.url("/").parse()?,
+            accepting_url.clone(),
+        ])
+        .with_engine_request_compression();
+
+        eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await?;
+
+        eth1_api.set_active_endpoint(&accepting_url).await?;
+
+        eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await?;
+
+        rejected_mock.assert_hits(1);
+        uncompressed_mock.assert_hits(1);
+        compressed_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_engine_request_compression_is_kept_after_parse_error() -> Result<()> {
+        let server = MockServer::start();
+
+        let compressed_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .header("content-encoding", "gzip");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "error": {
+                        "code": -32700,
+                        "message": "parse error",
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let uncompressed_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .header_missing("content-encoding");
+
+            then.status(500);
+        });
+
+        let eth1_api =
+            eth1_api_with_urls(vec![server.url("/").parse()?]).with_engine_request_compression();
+
+        for _ in 0..2 {
+            eth1_api
+                .new_payload::<Mainnet>(default_payload(), None)
+                .await
+                .expect_err("parse errors should be returned as usual");
+        }
+
+        compressed_mock.assert_hits(2);
+        uncompressed_mock.assert_hits(0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_malformed_response_is_reported_with_raw_body() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "latestValidHash": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let error = eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await
+            .expect_err("response without status should be rejected");
+
+        let Some(Error::MalformedResponse { method, raw, .. }) = error.downcast_ref() else {
+            panic!("unexpected error: {error:?}");
+        };
+
+        assert_eq!(method, "engine_newPayloadV1");
+        assert_eq!(raw, &json!({ "latestValidHash": null }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_response_without_result_is_reported_with_raw_body() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let error = eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await
+            .expect_err("response without result should be rejected");
+
+        let Some(Error::MalformedResponse { method, raw, .. }) = error.downcast_ref() else {
+            panic!("unexpected error: {error:?}");
+        };
+
+        assert_eq!(method, "engine_newPayloadV1");
+        assert_eq!(raw, &json!({ "jsonrpc": "2.0", "id": 0 }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_response_message_truncates_raw_body() {
+        let raw = json!({ "transactions": ["0x00".repeat(1000)] });
+        let raw_length = raw.to_string().len();
+
+        let message = Error::MalformedResponse {
+            method: "engine_getPayloadV2".to_owned(),
+            raw,
+            error: serde::de::Error::missing_field("executionPayload"),
+        }
+        .to_string();
+
+        assert!(message.len() < MAX_RESPONSE_BODY_IN_ERROR + 200);
+        assert!(message.ends_with(&format!("... ({raw_length} bytes in total))")));
+    }
+
+    #[test]
+    fn test_non_json_response_message_truncates_body() {
+        let message = Error::NonJsonResponse {
+            message: "<html>".repeat(1000),
+        }
+        .to_string();
+
+        assert!(message.len() < MAX_RESPONSE_BODY_IN_ERROR + 200);
+        assert!(message.ends_with("... (6000 bytes in total)"));
+    }
+
+    #[test_case("short body" => "short body"; "short body")]
+    #[test_case(
+        &"a".repeat(MAX_RESPONSE_BODY_IN_ERROR) => "a".repeat(MAX_RESPONSE_BODY_IN_ERROR);
+        "body at limit"
+    )]
+    #[test_case(
+        &format!("a{}", "é".repeat(200))
+            => format!("a{}... (401 bytes in total)", "é".repeat(149));
+        "prefix ends at character boundary"
+    )]
+    fn test_truncate_body(body: &str) -> String {
+        truncate_body(body).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_are_retried_after_exhaustion_grace() -> Result<()> {
+        let server = MockServer::start_async().await;
+
+        let failing_mock = server
+            .mock_async(|when, then| {
+                when.method(Method::POST).path("/");
+                then.status(500);
+            })
+            .await;
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?])
+            .with_exhaustion_grace(Duration::from_millis(200));
+
+        let recover = async {
+            time::sleep(Duration::from_millis(50)).await;
+
+            failing_mock.delete_async().await;
+
+            server
+                .mock_async(|when, then| {
+                    when.method(Method::POST).path("/");
+                    then.status(200).body(
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": 0,
+                            "result": "0x1d243",
+                        })
+                        .to_string(),
+                    );
+                })
+                .await;
+        };
+
+        let start = Instant::now();
+        let (head_number, ()) = tokio::join!(eth1_api.current_head_number(), recover);
+
+        assert_eq!(head_number?, 119_363);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_endpoint_is_retried_before_switching() -> Result<()> {
+        let server = MockServer::start_async().await;
+        let fallback_server = MockServer::start_async().await;
+
+        let rate_limited_mock = server
+            .mock_async(|when, then| {
+                when.method(Method::POST).path("/");
+                then.status(429).header("Retry-After", "1");
+            })
+            .await;
+
+        let fallback_mock = fallback_server
+            .mock_async(|when, then| {
+                when.method(Method::POST).path("/");
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": "0x0",
+                    })
+                    .to_string(),
+                );
+            })
+            .await;
+
+        let eth1_api = eth1_api_with_urls(vec![
+            server.url("/").parse()?,
+            fallback_server.url("/").parse()?,
+        ])
+        .with_rate_limit_backoff(Duration::from_millis(200));
+
+        let recover = async {
+            time::sleep(Duration::from_millis(50)).await;
+
+            rate_limited_mock.delete_async().await;
+
+            server
+                .mock_async(|when, then| {
+                    when.method(Method::POST).path("/");
+                    then.status(200).body(
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": 0,
+                            "result": "0x1d243",
+                        })
+                        .to_string(),
+                    );
+                })
+                .await;
+        };
+
+        let (head_number, ()) = tokio::join!(eth1_api.current_head_number(), recover);
+
+        assert_eq!(head_number?, 119_363);
+        assert_eq!(eth1_api.endpoint_switches.load(Ordering::Relaxed), 0);
+
+        fallback_mock.assert_hits_async(0).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_endpoint_is_retried_after_retry_after_delay() -> Result<()> {
+        let server = MockServer::start_async().await;
+        let fallback_server = MockServer::start_async().await;
+
+        let rate_limited_mock = server
+            .mock_async(|when, then| {
+                when.method(Method::POST).path("/");
+                then.status(429).header("Retry-After", "1");
+            })
+            .await;
+
+        let fallback_mock = fallback_server
+            .mock_async(|when, then| {
+                when.method(Method::POST).path("/");
+                then.status(200);
+            })
+            .await;
+
+        // No fixed backoff is configured. The delay comes from the header alone.
+        // Headers are only seen for compressed requests.
+        let eth1_api = eth1_api_with_urls(vec![
+            server.url("/").parse()?,
+            fallback_server.url("/").parse()?,
+        ])
+        .with_engine_request_compression();
+
+        let recover = async {
+            time::sleep(Duration::from_millis(50)).await;
+
+            rate_limited_mock.delete_async().await;
+
+            server
+                .mock_async(|when, then| {
+                    when.method(Method::POST).path("/");
+                    then.status(200).body(
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": 0,
+                            "result": {
+                                "status": "VALID",
+                                "latestValidHash": null,
+                                "validationError": null,
+                            },
+                        })
+                        .to_string(),
+                    );
+                })
+                .await;
+        };
+
+        let start = Instant::now();
+        let (payload_status, ()) = tokio::join!(
+            eth1_api.new_payload::<Mainnet>(default_payload(), None),
+            recover,
+        );
+
+        assert_eq!(payload_status?.status, PayloadValidationStatus::Valid);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(eth1_api.endpoint_switches.load(Ordering::Relaxed), 0);
+
+        fallback_mock.assert_hits_async(0).await;
+
+        Ok(())
+    }
+
+    // `web3` does not expose response headers.
+    // Requests made through it can only be retried after `rate_limit_backoff`.
+    #[tokio::test]
+    async fn test_retry_after_is_ignored_for_requests_made_through_web3() -> Result<()> {
+        let server = MockServer::start_async().await;
+        let fallback_server = MockServer::start_async().await;
+
+        let rate_limited_mock = server
+            .mock_async(|when, then| {
+                when.method(Method::POST).path("/");
+                then.status(429).header("Retry-After", "1");
+            })
+            .await;
+
+        let fallback_mock = fallback_server
+            .mock_async(|when, then| {
+                when.method(Method::POST).path("/");
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": eth1_block_json(100, H256::repeat_byte(1), H256::zero()),
+                    })
+                    .to_string(),
+                );
+            })
+            .await;
+
+        let eth1_api = eth1_api_with_urls(vec![
+            server.url("/").parse()?,
+            fallback_server.url("/").parse()?,
+        ]);
+
+        let start = Instant::now();
+        let block = eth1_api
+            .get_block_by_number(100)
+            .await?
+            .expect("block should be found on fallback endpoint");
+
+        assert_eq!(block.number, 100);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(eth1_api.endpoint_switches.load(Ordering::Relaxed), 1);
+
+        rate_limited_mock.assert_hits_async(1).await;
+        fallback_mock.assert_hits_async(1).await;
+
+        Ok(())
+    }
+
+    #[test_case("5" => Some(Duration::from_secs(5)))]
+    #[test_case(" 7 " => Some(Duration::from_secs(7)))]
+    #[test_case("Wed, 21 Oct 2015 07:28:00 GMT" => None)]
+    fn test_retry_after(value: &str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            RETRY_AFTER,
+            value.parse().expect("header value should be valid"),
+        );
+        retry_after(&headers)
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_exhausted_without_grace() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(500);
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let error = eth1_api
+            .current_head_number()
+            .await
+            .expect_err("failing endpoint should exhaust endpoints");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::EndpointsExhausted),
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_connections_are_reused_across_calls() -> Result<()> {
+        let server = MockServer::start();
+
+        let mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("eth_blockNumber");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": "0x64",
+                })
+                .to_string(),
+            );
+        });
+
+        // Forward connections to the mock server and count them.
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let proxy_url = format!("http://{}/", listener.local_addr()?).parse()?;
+        let target = *server.address();
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        tokio::spawn({
+            let connections = connections.clone_arc();
+
+            async move {
+                while let Ok((mut inbound, _)) = listener.accept().await {
+                    connections.fetch_add(1, Ordering::Relaxed);
+
+                    tokio::spawn(async move {
+                        if let Ok(mut outbound) = TcpStream::connect(target).await {
+                            copy_bidirectional(&mut inbound, &mut outbound).await.ok();
+                        }
+                    });
+                }
+            }
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![proxy_url]);
+
+        for _ in 0..3 {
+            assert_eq!(eth1_api.current_head_number().await?, 100);
+        }
+
+        mock.assert_hits(3);
+
+        assert_eq!(connections.load(Ordering::Relaxed), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_identical_concurrent_engine_calls_are_coalesced() -> Result<()> {
+        let server = MockServer::start();
+
+        let mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV2");
+
+            then.status(200).delay(Duration::from_millis(200)).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": "VALID",
+                        "latestValidHash": H256::zero(),
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api =
+            eth1_api_with_urls(vec![server.url("/").parse()?]).with_engine_call_coalescing();
+
+        let payload = || CapellaExecutionPayload::<Mainnet>::default().into();
+
+        let (first, second) = tokio::join!(
+            eth1_api.new_payload::<Mainnet>(payload(), None),
+            eth1_api.new_payload::<Mainnet>(payload(), None),
+        );
+
+        mock.assert_hits(1);
+
+        assert!(first?.status.is_valid());
+        assert!(second?.status.is_valid());
+        assert!(eth1_api
+            .in_flight_engine_calls
+            .lock()
+            .expect("in-flight engine call mutex is poisoned")
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test_case("engine_newPayloadV3" => Some(("engine_newPayload", "V3")))]
+    #[test_case("engine_getClientVersionV1" => Some(("engine_getClientVersion", "V1")))]
+    #[test_case("engine_exchangeCapabilities" => None)]
+    #[test_case("eth_getBlockByNumber" => None)]
+    fn test_split_method_version(method: &str) -> Option<(&str, &str)> {
+        split_method_version(method)
+    }
+
+    #[test_case(
+        Web3Error::Transport(TransportError::Message(format!(
+            "{WEB3_DESERIALIZATION_ERROR_PREFIX}expected value at line 1 column 1: <html></html>",
+        ))) => true;
+        "HTML body in web3 message"
+    )]
+    #[test_case(
+        Web3Error::Transport(TransportError::Message(format!(
+            "{WEB3_DESERIALIZATION_ERROR_PREFIX}data did not match any variant: \
+             {{\"jsonrpc\":\"2.0\",\"id\":0}}",
+        ))) => false;
+        "JSON-RPC body without result in web3 message"
+    )]
+    #[test_case(Web3Error::InvalidResponse("<html></html>".to_owned()) => true; "HTML body")]
+    #[test_case(Web3Error::InvalidResponse("null".to_owned()) => true; "JSON body without version")]
+    #[test_case(
+        Web3Error::InvalidResponse(r#"{"jsonrpc":"2.0","id":0}"#.to_owned()) => false;
+        "JSON-RPC body without result"
+    )]
+    #[test_case(
+        Web3Error::Decoder("invalid type: string, expected u64".to_owned()) => false;
+        "result of unexpected shape"
+    )]
+    #[test_case(Web3Error::Transport(TransportError::Code(502)) => false; "status code")]
+    fn test_classify_web3_error_as_non_json(error: Web3Error) -> bool {
+        matches!(
+            classify_web3_error(error).downcast_ref(),
+            Some(Error::NonJsonResponse { .. }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_html_response_is_reported_as_non_json() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200)
+                .header("content-type", "text/html")
+                .body("<html><body><h1>502 Bad Gateway</h1></body></html>");
+        });
+
+        let eth1_api = default_eth1_api();
+
+        let error = eth1_api
+            .get_payload_from_endpoint::<Mainnet>(
+                Some(&server.url("/").parse()?),
+                PayloadId::Bellatrix(H64::zero()),
+                ENGINE_GET_PAYLOAD_TIMEOUT,
+            )
+            .await
+            .expect_err("HTML response should be rejected");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::NonJsonResponse { .. }),
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_html_response_is_reported_as_non_json_after_fallback() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200)
+                .header("content-type", "text/html")
+                .body("<html><body><h1>502 Bad Gateway</h1></body></html>");
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?]);
+
+        let error = eth1_api
+            .new_payload::<Mainnet>(default_payload(), None)
+            .await
+            .expect_err("HTML response should be rejected");
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(Error::EndpointsExhausted),
+        ));
+
+        assert!(error
+            .chain()
+            .any(|cause| matches!(cause.downcast_ref(), Some(Error::NonJsonResponse { .. }),)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_are_limited() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200).delay(Duration::from_millis(100)).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": "0x1d243",
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api =
+            eth1_api_with_urls(vec![server.url("/").parse()?]).with_max_concurrent_requests(2);
+
+        let start = Instant::now();
+
+        let head_numbers =
+            future::try_join_all((0..6).map(|_| eth1_api.current_head_number())).await?;
+
+        assert_eq!(head_numbers, [119_363; 6]);
+
+        // 6 requests with at most 2 in flight take at least 3 round trips.
+        assert!(start.elapsed() >= Duration::from_millis(300));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_permit_is_not_held_during_rate_limit_backoff() -> Result<()> {
+        let server = MockServer::start_async().await;
+
+        server
+            .mock_async(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("eth_blockNumber");
+                then.status(429);
+            })
+            .await;
+
+        server
+            .mock_async(|when, then| {
+                when.method(Method::POST)
+                    .path("/")
+                    .body_contains("eth_chainId");
+                then.status(200).body(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": 0,
+                        "result": "0x1",
+                    })
+                    .to_string(),
+                );
+            })
+            .await;
+
+        let eth1_api = eth1_api_with_urls(vec![server.url("/").parse()?])
+            .with_max_concurrent_requests(1)
+            .with_rate_limit_backoff(Duration::from_millis(300));
+
+        let other_request = async {
+            time::sleep(Duration::from_millis(50)).await;
+
+            let start = Instant::now();
+
+            let (chain_id, _) = eth1_api
+                .execute_with_report::<U64>(None, "eth_chainId", vec![], None)
+                .await;
+
+            (chain_id, start.elapsed())
+        };
+
+        let (head_number, (chain_id, elapsed)) =
+            tokio::join!(eth1_api.current_head_number(), other_request);
+
+        assert!(head_number.is_err());
+        assert_eq!(chain_id?, U64::one());
+
+        // The rate limited request is waiting out its backoff while the other one is sent.
+        assert!(elapsed < Duration::from_millis(250));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_request_report_counts_retries() -> Result<()> {
+        let failing_server = MockServer::start();
+        let working_server = MockServer::start();
+
+        failing_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(500);
+        });
+
+        working_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": "0x1d243",
+                })
+                .to_string(),
+            );
+        });
+
+        let eth1_api = eth1_api_with_urls(vec![
+            failing_server.url("/").parse()?,
+            working_server.url("/").parse()?,
+        ]);
+
+        let (result, report) = eth1_api
+            .execute_with_report::<U64>(None, "eth_blockNumber", vec![], None)
+            .await;
+
+        assert_eq!(result?, U64::from(119_363));
+        assert_eq!(report.endpoints_tried, 2);
+        assert_eq!(report.retries(), 1);
+        assert!(report.elapsed > Duration::ZERO);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_tried_are_recorded() -> Result<()> {
+        let failing_server = MockServer::start();
+        let working_server = MockServer::start();
+
+        failing_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(500);
+        });
+
+        working_server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": "0x1d243",
+                })
+                .to_string(),
+            );
+        });
+
+        let config = Arc::new(Config::mainnet());
+        let auth = Arc::default();
+        let metrics = Arc::new(Metrics::new()?);
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config,
+            Client::new(),
+            auth,
+            vec![
+                failing_server.url("/").parse()?,
+                working_server.url("/").parse()?,
+            ],
+            None,
+            Some(metrics.clone_arc()),
+        ));
+
+        assert_eq!(eth1_api.current_head_number().await?, 119_363);
+        assert_eq!(metrics.eth1_api_endpoints_tried_last.get(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_requests_succeed_after_metrics_receiver_is_dropped() -> Result<()> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "result": "0x1d243",
+        });
+
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200).body(body.to_string());
+        });
+
+        let config = Arc::new(Config::mainnet());
+        let auth = Arc::default();
+        let server_url = server.url("/").parse()?;
+        let (eth1_api_to_metrics_tx, eth1_api_to_metrics_rx) = mpsc::unbounded();
+
+        drop(eth1_api_to_metrics_rx);
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config,
+            Client::new(),
+            auth,
+            vec![server_url],
+            Some(eth1_api_to_metrics_tx),
+            None,
+        ));
+
+        assert_eq!(eth1_api.current_head_number().await?, 119_363);
+        assert!(eth1_api.metrics_channel_closed.load(Ordering::Relaxed));
+        assert_eq!(eth1_api.current_head_number().await?, 119_363);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_endpoint_fails_over_promptly() -> Result<()> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "result": "0x1d243",
+        });
+
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200).body(body.to_string());
+        });
+
+        let config = Arc::new(Config::mainnet());
+        let auth = Arc::default();
+        let server_url = server.url("/").parse()?;
+
+        // Addresses in `10.255.255.0/24` are not routed, so connecting to them hangs
+        // until the connect timeout is reached.
+        let unreachable_url = "http://10.255.255.1:8545".parse()?;
+
+        let client = Client::builder()
+            .connect_timeout(Duration::from_millis(100))
+            .build()?;
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config,
+            client,
+            auth,
+            vec![unreachable_url, server_url],
+            None,
+            None,
+        ));
+
+        let start = Instant::now();
+        let head_number = eth1_api.current_head_number().await?;
+
+        assert_eq!(head_number, 119_363);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::{bail, Error as AnyhowError, Result};
+use serde_json::Value;
 use ssz::{ContiguousList, Ssz};
 use thiserror::Error;
 use typenum::U4294967296;
@@ -9,15 +10,16 @@ use types::{
         ExecutionBlockHash, ExecutionBlockNumber, ExecutionTransactionHash, UnixSeconds,
     },
 };
-use web3::types::{Block, U64};
+use web3::types::{Block, U256, U64};
 
 use crate::DepositEvent;
 
 type MaxDepositEvents = U4294967296;
 
-#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
 enum Error {
+    #[error("RPC returned block with invalid quantity: {value}")]
+    InvalidQuantity { value: Value },
     #[error("RPC returned block without hash: {block:?}")]
     MissingHash {
         block: Block<ExecutionTransactionHash>,
@@ -33,6 +35,7 @@ enum Error {
 }
 
 #[derive(Default, Debug, Ssz)]
+#[cfg_attr(any(test, feature = "test-util"), derive(PartialEq, Eq))]
 #[ssz(derive_hash = false)]
 pub struct Eth1Block {
     pub hash: ExecutionBlockHash,
@@ -43,44 +46,97 @@ pub struct Eth1Block {
     pub deposit_events: ContiguousList<DepositEvent, MaxDepositEvents>,
 }
 
+#[cfg(any(test, feature = "test-util"))]
+impl Eth1Block {
+    #[must_use]
+    pub const fn with_hash(mut self, hash: ExecutionBlockHash) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_parent_hash(mut self, parent_hash: ExecutionBlockHash) -> Self {
+        self.parent_hash = parent_hash;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_number(mut self, number: ExecutionBlockNumber) -> Self {
+        self.number = number;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_timestamp(mut self, timestamp: UnixSeconds) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_total_difficulty(mut self, total_difficulty: Difficulty) -> Self {
+        self.total_difficulty = total_difficulty;
+        self
+    }
+
+    pub fn with_deposit_events(mut self, deposit_events: Vec<DepositEvent>) -> Result<Self> {
+        self.deposit_events = deposit_events.try_into()?;
+        Ok(self)
+    }
+}
+
 impl TryFrom<Block<ExecutionTransactionHash>> for Eth1Block {
     type Error = AnyhowError;
 
     fn try_from(block: Block<ExecutionTransactionHash>) -> Result<Self, Self::Error> {
-        let Block {
-            hash,
-            parent_hash,
-            number,
-            timestamp,
-            total_difficulty,
-            ..
-        } = block;
+        convert(block, false)
+    }
+}
 
-        let Some(hash) = hash else {
-            bail!(Error::MissingHash { block });
-        };
+/// Converts a block returned for the `pending` tag.
+///
+/// Pending blocks are not sealed, so execution clients may leave out some of their fields.
+/// A missing hash or total difficulty is replaced with zero.
+pub fn try_from_pending_block(block: Block<ExecutionTransactionHash>) -> Result<Eth1Block> {
+    convert(block, true)
+}
 
-        let Some(number) = number.as_ref().map(U64::as_u64) else {
-            bail!(Error::MissingNumber { block });
-        };
+fn convert(block: Block<ExecutionTransactionHash>, pending: bool) -> Result<Eth1Block> {
+    let Block {
+        hash,
+        parent_hash,
+        number,
+        timestamp,
+        total_difficulty,
+        ..
+    } = block;
 
-        // `<U256 as TryInto<UnixSeconds>>::Error` is `&'static str`.
-        let timestamp = timestamp.try_into().map_err(AnyhowError::msg)?;
+    let hash = match hash {
+        Some(hash) => hash,
+        None if pending => ExecutionBlockHash::zero(),
+        None => bail!(Error::MissingHash { block }),
+    };
 
-        let total_difficulty = match total_difficulty {
-            Some(total_difficulty) => total_difficulty.into(),
-            None => bail!(Error::MissingTotalDifficulty { block }),
-        };
+    let Some(number) = number.as_ref().map(U64::as_u64) else {
+        bail!(Error::MissingNumber { block });
+    };
 
-        Ok(Self {
-            hash,
-            parent_hash,
-            number,
-            timestamp,
-            total_difficulty,
-            deposit_events: ContiguousList::default(),
-        })
-    }
+    // `<U256 as TryInto<UnixSeconds>>::Error` is `&'static str`.
+    let timestamp = timestamp.try_into().map_err(AnyhowError::msg)?;
+
+    let total_difficulty = match total_difficulty {
+        Some(total_difficulty) => total_difficulty.into(),
+        None if pending => Difficulty::ZERO,
+        None => bail!(Error::MissingTotalDifficulty { block }),
+    };
+
+    Ok(Eth1Block {
+        hash,
+        parent_hash,
+        number,
+        timestamp,
+        total_difficulty,
+        deposit_events: ContiguousList::default(),
+    })
 }
 
 impl From<Eth1Block> for TimedPowBlock {
@@ -103,3 +159,41 @@ impl From<Eth1Block> for TimedPowBlock {
         }
     }
 }
+
+/// Rewrites quantities in a block returned by `eth_getBlockByHash` or `eth_getBlockByNumber`
+/// as prefixed hexadecimal strings.
+///
+/// Some execution clients and mocks encode `number` and other quantities as decimal strings or
+/// JSON numbers, but [`Block`] only accepts prefixed hexadecimal ones. Quantities are the only
+/// fields of a block that can consist of decimal digits alone, so all such fields are rewritten.
+pub fn normalize_quantities(block: &mut Value) -> Result<()> {
+    let Value::Object(fields) = block else {
+        return Ok(());
+    };
+
+    for value in fields.values_mut() {
+        let quantity = match value {
+            Value::Number(number) => match number.as_u64() {
+                Some(number) => U256::from(number),
+                None => bail!(Error::InvalidQuantity {
+                    value: value.clone(),
+                }),
+            },
+            Value::String(string) if is_decimal(string) => match U256::from_dec_str(string) {
+                Ok(quantity) => quantity,
+                Err(_) => bail!(Error::InvalidQuantity {
+                    value: value.clone(),
+                }),
+            },
+            _ => continue,
+        };
+
+        *value = Value::String(format!("{quantity:#x}"));
+    }
+
+    Ok(())
+}
+
+fn is_decimal(string: &str) -> bool {
+    !string.is_empty() && string.bytes().all(|byte| byte.is_ascii_digit())
+}
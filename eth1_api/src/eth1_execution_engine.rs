@@ -1,12 +1,17 @@
+use core::time::Duration;
 use std::sync::Arc;
 
 use anyhow::Result;
-use derive_more::Constructor;
 use either::Either;
-use execution_engine::{ExecutionEngine, PayloadAttributes, PayloadId, PayloadStatusV1};
+use execution_engine::{
+    ExecutionEngine, PayloadAttributes, PayloadId, PayloadStatusV1, PowBlockLookup,
+};
 use futures::channel::{mpsc::UnboundedSender, oneshot::Sender};
 use log::{info, warn};
-use tokio::runtime::{Builder, Handle};
+use tokio::{
+    runtime::{Builder, Handle},
+    time::{self, error::Elapsed},
+};
 use types::{
     combined::{ExecutionPayload, ExecutionPayloadParams},
     config::Config,
@@ -18,11 +23,15 @@ use web3::types::U64;
 
 use crate::{eth1_api::Eth1Api, messages::ExecutionServiceMessage};
 
-#[derive(Constructor)]
+// `ExecutionEngine::pow_block` is called synchronously from fork choice.
+// An execution client that never responds would stall fork choice without this.
+const POW_BLOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
 pub struct Eth1ExecutionEngine<P: Preset> {
     config: Arc<Config>,
     eth1_api: Arc<Eth1Api>,
     execution_service_tx: UnboundedSender<ExecutionServiceMessage<P>>,
+    pow_block_timeout: Duration,
 }
 
 impl<P: Preset> ExecutionEngine<P> for Eth1ExecutionEngine<P> {
@@ -80,7 +89,10 @@ impl<P: Preset> ExecutionEngine<P> for Eth1ExecutionEngine<P> {
                 .enable_io()
                 .enable_time()
                 .build()?
-                .block_on(self.eth1_api.get_block_by_hash(block_hash))
+                .block_on(time::timeout(
+                    self.pow_block_timeout,
+                    self.eth1_api.get_block_by_hash(block_hash),
+                ))?
         };
 
         // Start the new runtime in a scoped thread if called from a Tokio thread.
@@ -105,21 +117,49 @@ impl<P: Preset> ExecutionEngine<P> for Eth1ExecutionEngine<P> {
         match result {
             Ok(Some(pow_block)) => {
                 info!("request for Eth1 block {block_hash:?} returned {pow_block:?}");
-                Some(pow_block.into())
+                PowBlockLookup::Found(pow_block.into())
             }
             Ok(None) => {
                 warn!("Eth1 block {block_hash:?} not found");
-                None
+                PowBlockLookup::NotFound
+            }
+            Err(error) if error.is::<Elapsed>() => {
+                warn!(
+                    "request for Eth1 block {block_hash:?} timed out after {:?}",
+                    self.pow_block_timeout,
+                );
+                PowBlockLookup::TimedOut
             }
             Err(error) => {
                 warn!("request for Eth1 block {block_hash:?} failed: {error:?}");
-                None
+                PowBlockLookup::NotFound
             }
         }
     }
 }
 
 impl<P: Preset> Eth1ExecutionEngine<P> {
+    #[must_use]
+    pub const fn new(
+        config: Arc<Config>,
+        eth1_api: Arc<Eth1Api>,
+        execution_service_tx: UnboundedSender<ExecutionServiceMessage<P>>,
+    ) -> Self {
+        Self {
+            config,
+            eth1_api,
+            execution_service_tx,
+            pow_block_timeout: POW_BLOCK_TIMEOUT,
+        }
+    }
+
+    /// Overrides how long [`ExecutionEngine::pow_block`] waits for the execution client.
+    #[must_use]
+    pub const fn with_pow_block_timeout(mut self, timeout: Duration) -> Self {
+        self.pow_block_timeout = timeout;
+        self
+    }
+
     pub async fn get_execution_payload(
         &self,
         payload_id: PayloadId,
@@ -167,3 +207,76 @@ impl<P: Preset> Eth1ExecutionEngine<P> {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::mpsc;
+    use httpmock::{Method, MockServer};
+    use reqwest::Client;
+    use serde_json::json;
+    use std_ext::ArcExt as _;
+    use types::preset::Mainnet;
+
+    use super::*;
+
+    #[test]
+    fn pow_block_gives_up_on_slow_execution_client() -> Result<()> {
+        let timeout = Duration::from_millis(50);
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200)
+                .delay(timeout * 20)
+                .body(json!({ "jsonrpc": "2.0", "id": 0, "result": null }).to_string());
+        });
+
+        let execution_engine = execution_engine(&server)?.with_pow_block_timeout(timeout);
+
+        assert!(matches!(
+            execution_engine.pow_block(H256::repeat_byte(1)),
+            PowBlockLookup::TimedOut,
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pow_block_reports_missing_block_as_not_found() -> Result<()> {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(Method::POST).path("/");
+            then.status(200)
+                .body(json!({ "jsonrpc": "2.0", "id": 0, "result": null }).to_string());
+        });
+
+        assert!(matches!(
+            execution_engine(&server)?.pow_block(H256::repeat_byte(1)),
+            PowBlockLookup::NotFound,
+        ));
+
+        Ok(())
+    }
+
+    fn execution_engine(server: &MockServer) -> Result<Eth1ExecutionEngine<Mainnet>> {
+        let config = Arc::new(Config::mainnet());
+
+        let eth1_api = Arc::new(Eth1Api::new(
+            config.clone_arc(),
+            Client::new(),
+            Arc::default(),
+            vec![server.url("/").parse()?],
+            None,
+            None,
+        ));
+
+        let (execution_service_tx, _execution_service_rx) = mpsc::unbounded();
+
+        Ok(Eth1ExecutionEngine::new(
+            config,
+            eth1_api,
+            execution_service_tx,
+        ))
+    }
+}
@@ -1,12 +1,20 @@
+use core::{future::Future, time::Duration};
 use std::sync::Arc;
 
 use anyhow::Result;
-use derive_more::Constructor;
 use either::Either;
 use execution_engine::{ForkChoiceUpdatedResponse, PayloadAttributes, PayloadStatusV1};
 use fork_choice_control::Wait;
-use futures::{channel::mpsc::UnboundedReceiver, StreamExt as _};
-use log::warn;
+use futures::{
+    channel::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot::Sender,
+    },
+    select, StreamExt as _,
+};
+use log::{debug, warn};
+use std_ext::ArcExt as _;
+use tokio::time;
 use types::{
     combined::{ExecutionPayload, ExecutionPayloadParams},
     nonstandard::Phase,
@@ -14,97 +22,192 @@ use types::{
     preset::Preset,
 };
 
-use crate::{eth1_api::Eth1Api, messages::ExecutionServiceMessage, misc::ApiController};
+use crate::{
+    eth1_api::{self, Eth1Api},
+    messages::ExecutionServiceMessage,
+    misc::ApiController,
+};
+
+/// How [`ExecutionService`] handles `SYNCING` responses to `engine_newPayload`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum SyncingPayloadPolicy {
+    /// Pass the response on, which makes fork choice import the block optimistically.
+    #[default]
+    Optimistic,
+    /// Resend the payload until the execution client returns a different status.
+    ///
+    /// Payloads are resent in a separate task. Other messages (including
+    /// `engine_forkchoiceUpdated` calls) are handled in the meantime, but fork choice does not
+    /// learn the status of the payload until the retries end.
+    /// The block is imported optimistically if the execution client is still syncing after that.
+    WaitForValid {
+        retry_delay: Duration,
+        max_retries: usize,
+    },
+}
+
+impl SyncingPayloadPolicy {
+    // Returns the delay between retries and the maximum number of them if `response` should be
+    // resent.
+    const fn retries(self, response: &PayloadStatusV1) -> Option<(Duration, usize)> {
+        match self {
+            Self::WaitForValid {
+                retry_delay,
+                max_retries,
+            } if max_retries > 0 && response.status.is_syncing() => {
+                Some((retry_delay, max_retries))
+            }
+            _ => None,
+        }
+    }
+}
 
-#[derive(Constructor)]
 pub struct ExecutionService<P: Preset, W: Wait> {
     api: Arc<Eth1Api>,
     controller: ApiController<P, W>,
     rx: UnboundedReceiver<ExecutionServiceMessage<P>>,
+    syncing_payload_policy: SyncingPayloadPolicy,
+    retried_payloads_tx: UnboundedSender<RetriedPayload<P>>,
+    retried_payloads_rx: UnboundedReceiver<RetriedPayload<P>>,
 }
 
 impl<P: Preset, W: Wait> ExecutionService<P, W> {
+    #[must_use]
+    pub fn new(
+        api: Arc<Eth1Api>,
+        controller: ApiController<P, W>,
+        rx: UnboundedReceiver<ExecutionServiceMessage<P>>,
+    ) -> Self {
+        let (retried_payloads_tx, retried_payloads_rx) = mpsc::unbounded();
+
+        Self {
+            api,
+            controller,
+            rx,
+            syncing_payload_policy: SyncingPayloadPolicy::default(),
+            retried_payloads_tx,
+            retried_payloads_rx,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_syncing_payload_policy(
+        mut self,
+        syncing_payload_policy: SyncingPayloadPolicy,
+    ) -> Self {
+        self.syncing_payload_policy = syncing_payload_policy;
+        self
+    }
+
     pub async fn run(mut self) -> Result<()> {
-        while let Some(message) = self.rx.next().await {
-            match message {
-                ExecutionServiceMessage::NotifyForkchoiceUpdated {
-                    head_eth1_block_hash,
-                    safe_eth1_block_hash,
-                    finalized_eth1_block_hash,
-                    payload_attributes,
-                    sender,
-                } => {
-                    let Some(response) = self
-                        .notify_forkchoice_updated(
-                            head_eth1_block_hash,
-                            safe_eth1_block_hash,
-                            finalized_eth1_block_hash,
-                            payload_attributes,
-                        )
-                        .await
-                    else {
-                        continue;
-                    };
+        loop {
+            select! {
+                message = self.rx.next() => match message {
+                    Some(message) => self.handle_message(message).await?,
+                    None => break,
+                },
+
+                retried_payload = self.retried_payloads_rx.select_next_some() => {
+                    let RetriedPayload {
+                        beacon_block_root,
+                        payload,
+                        response,
+                        sender,
+                    } = retried_payload;
 
-                    let ForkChoiceUpdatedResponse {
-                        payload_status,
-                        payload_id,
-                    } = response;
-
-                    self.controller
-                        .on_notified_fork_choice_update(payload_status);
-
-                    if let Some(sender) = sender {
-                        if let Err(message) = sender.send(payload_id) {
-                            warn!(
-                                "sending engine_forkchoiceUpdated result \
-                                 failed because the receiver was dropped: {message:?}"
-                            );
-                        }
+                    self.finish_new_payload(beacon_block_root, &payload, response, sender)?;
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_message(&self, message: ExecutionServiceMessage<P>) -> Result<()> {
+        match message {
+            ExecutionServiceMessage::NotifyForkchoiceUpdated {
+                head_eth1_block_hash,
+                safe_eth1_block_hash,
+                finalized_eth1_block_hash,
+                payload_attributes,
+                sender,
+            } => {
+                let Some(response) = self
+                    .notify_forkchoice_updated(
+                        head_eth1_block_hash,
+                        safe_eth1_block_hash,
+                        finalized_eth1_block_hash,
+                        payload_attributes,
+                    )
+                    .await
+                else {
+                    return Ok(());
+                };
+
+                let ForkChoiceUpdatedResponse {
+                    payload_status,
+                    payload_id,
+                } = response;
+
+                self.controller
+                    .on_notified_fork_choice_update(payload_status);
+
+                if let Some(sender) = sender {
+                    if let Err(message) = sender.send(payload_id) {
+                        warn!(
+                            "sending engine_forkchoiceUpdated result \
+                             failed because the receiver was dropped: {message:?}"
+                        );
                     }
                 }
-                ExecutionServiceMessage::NotifyNewPayload {
-                    beacon_block_root,
-                    payload,
-                    params,
-                    sender,
-                } => {
-                    let response = self
-                        .notify_new_payload(beacon_block_root, payload.clone(), params)
-                        .await;
-
-                    match &response {
-                        Ok(payload_status) => {
-                            self.controller.on_notified_new_payload(
-                                payload.block_hash(),
-                                payload_status.clone(),
-                            );
-                        }
-                        Err(error) => {
-                            warn!("engine_newPayload call failed: {error}");
-
-                            features::log!(
-                                DebugEth1,
-                                "engine_newPayload call failed \
-                                 (beacon block root: {beacon_block_root:?}, \
-                                  payload: {payload:?}, error: {error})",
-                            );
-                        }
-                    }
 
-                    if let Some(sender) = sender {
-                        if let Err(message) = sender.send(response) {
-                            warn!(
-                                "sending engine_newPayload result \
-                                failed because the receiver was dropped: {message:?}"
-                            );
-                        }
+                Ok(())
+            }
+            ExecutionServiceMessage::NotifyNewPayload {
+                beacon_block_root,
+                payload,
+                params,
+                sender,
+            } => {
+                let response = self.api.new_payload(payload.clone(), params.clone()).await;
+
+                let retries = match &response {
+                    Ok(payload_status) => self
+                        .syncing_payload_policy
+                        .retries(payload_status)
+                        .map(|retries| (retries, payload_status.clone())),
+                    Err(_) => None,
+                };
+
+                let Some(((retry_delay, max_retries), response)) = retries else {
+                    return self.finish_new_payload(beacon_block_root, &payload, response, sender);
+                };
+
+                // Waiting for the execution client here would hold up all other messages.
+                let api = self.api.clone_arc();
+                let retried_payloads_tx = self.retried_payloads_tx.clone();
+
+                tokio::spawn(async move {
+                    let response = wait_for_valid(response, retry_delay, max_retries, || {
+                        api.new_payload(payload.clone(), params.clone())
+                    })
+                    .await;
+
+                    let retried_payload = RetriedPayload {
+                        beacon_block_root,
+                        payload,
+                        response,
+                        sender,
+                    };
+
+                    if retried_payloads_tx.unbounded_send(retried_payload).is_err() {
+                        debug!("send to execution service failed because the receiver was dropped");
                     }
-                }
+                });
+
+                Ok(())
             }
         }
-
-        Ok(())
     }
 
     async fn notify_forkchoice_updated(
@@ -161,36 +264,405 @@ impl<P: Preset, W: Wait> ExecutionService<P, W> {
         }
     }
 
-    async fn notify_new_payload(
+    fn finish_new_payload(
         &self,
         beacon_block_root: H256,
-        payload: ExecutionPayload<P>,
-        params: Option<ExecutionPayloadParams>,
-    ) -> Result<PayloadStatusV1> {
-        let block_number = payload.block_number();
-        let block_hash = payload.block_hash();
-        let response = self.api.new_payload(payload, params).await?;
-
-        if response.status.is_invalid() {
-            warn!(
-                "engine_newPayload returned INVALID status \
-                 (beacon_block_root: {beacon_block_root:?}, \
-                  block_number: {block_number}, \
-                  block_hash: {block_hash:?}, \
-                  response: {response:?})",
-            );
+        payload: &ExecutionPayload<P>,
+        response: Result<PayloadStatusV1>,
+        sender: Option<Sender<Result<PayloadStatusV1>>>,
+    ) -> Result<()> {
+        // See `Eth1Api::with_fatal_invalid_payloads`.
+        // Stopping here halts the node before fork choice learns about the payload.
+        if response
+            .as_ref()
+            .is_err_and(eth1_api::is_fatal_invalid_payload)
+        {
+            return response.map(|_| ());
+        }
+
+        match &response {
+            Ok(payload_status) => {
+                log_payload_status(beacon_block_root, payload, payload_status);
+
+                self.controller
+                    .on_notified_new_payload(payload.block_hash(), payload_status.clone());
+            }
+            Err(error) => {
+                warn!("engine_newPayload call failed: {error}");
+
+                features::log!(
+                    DebugEth1,
+                    "engine_newPayload call failed \
+                     (beacon block root: {beacon_block_root:?}, \
+                      payload: {payload:?}, error: {error})",
+                );
+            }
         }
 
-        if response.status.is_syncing() {
-            warn!(
-                "engine_newPayload returned SYNCING status \
-                 (beacon_block_root: {beacon_block_root:?}, \
-                  block_number: {block_number}, \
-                  block_hash: {block_hash:?}, \
-                  response: {response:?})",
+        if let Some(sender) = sender {
+            if let Err(message) = sender.send(response) {
+                warn!(
+                    "sending engine_newPayload result \
+                    failed because the receiver was dropped: {message:?}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// A payload resent as configured by `SyncingPayloadPolicy::WaitForValid`.
+struct RetriedPayload<P: Preset> {
+    beacon_block_root: H256,
+    payload: ExecutionPayload<P>,
+    response: Result<PayloadStatusV1>,
+    sender: Option<Sender<Result<PayloadStatusV1>>>,
+}
+
+fn log_payload_status<P: Preset>(
+    beacon_block_root: H256,
+    payload: &ExecutionPayload<P>,
+    response: &PayloadStatusV1,
+) {
+    let block_number = payload.block_number();
+    let block_hash = payload.block_hash();
+
+    if response.status.is_invalid() {
+        warn!(
+            "engine_newPayload returned INVALID status \
+             (beacon_block_root: {beacon_block_root:?}, \
+              block_number: {block_number}, \
+              block_hash: {block_hash:?}, \
+              response: {response:?})",
+        );
+    }
+
+    if response.status.is_syncing() {
+        warn!(
+            "engine_newPayload returned SYNCING status \
+             (beacon_block_root: {beacon_block_root:?}, \
+              block_number: {block_number}, \
+              block_hash: {block_hash:?}, \
+              response: {response:?})",
+        );
+    }
+}
+
+async fn wait_for_valid<F: Future<Output = Result<PayloadStatusV1>>>(
+    mut response: PayloadStatusV1,
+    retry_delay: Duration,
+    max_retries: usize,
+    mut resend: impl FnMut() -> F,
+) -> Result<PayloadStatusV1> {
+    for retry in 1..=max_retries {
+        if !response.status.is_syncing() {
+            break;
+        }
+
+        warn!(
+            "execution client is syncing; resending payload in {retry_delay:?} \
+             (retry {retry} of {max_retries})",
+        );
+
+        time::sleep(retry_delay).await;
+
+        response = resend().await?;
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use anyhow::bail;
+    use clock::Tick;
+    use database::Database;
+    use execution_engine::PayloadValidationStatus;
+    use fork_choice_control::{Controller, Storage, DEFAULT_ARCHIVAL_EPOCH_INTERVAL};
+    use fork_choice_store::StoreConfig;
+    use futures::{channel::oneshot, FutureExt as _};
+    use httpmock::{Method, Mock, MockServer};
+    use reqwest::Client;
+    use serde_json::json;
+    use test_case::test_case;
+    use types::{
+        bellatrix::containers::ExecutionPayload as BellatrixExecutionPayload, config::Config,
+        preset::Minimal,
+    };
+
+    use crate::eth1_execution_engine::Eth1ExecutionEngine;
+
+    use super::*;
+
+    const WAIT_FOR_VALID: SyncingPayloadPolicy = SyncingPayloadPolicy::WaitForValid {
+        retry_delay: Duration::from_millis(100),
+        max_retries: 10,
+    };
+
+    #[tokio::test]
+    async fn test_execution_service_stops_on_fatal_invalid_payload() -> Result<()> {
+        let server = MockServer::start();
+        let invalid_mock = mock_new_payload(&server, "INVALID");
+        let eth1_api = Arc::new(eth1_api(&server)?.with_fatal_invalid_payloads());
+        let (execution_service, execution_service_tx, _mutator_handle) =
+            execution_service(eth1_api)?;
+
+        ExecutionServiceMessage::NotifyNewPayload {
+            beacon_block_root: H256::zero(),
+            payload: BellatrixExecutionPayload::default().into(),
+            params: None,
+            sender: None,
+        }
+        .send(&execution_service_tx);
+
+        // The service would keep running without a fatal error because
+        // `Eth1ExecutionEngine` holds on to a sender.
+        let error = execution_service
+            .run()
+            .await
+            .expect_err("INVALID status should stop the service");
+
+        assert!(eth1_api::is_fatal_invalid_payload(&error));
+
+        invalid_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_valid_retries_do_not_hold_up_other_messages() -> Result<()> {
+        let server = MockServer::start();
+        let syncing_mock = mock_new_payload(&server, "SYNCING");
+
+        let forkchoice_updated_mock = server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_forkchoiceUpdatedV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "payloadStatus": {
+                            "status": "VALID",
+                            "latestValidHash": null,
+                            "validationError": null,
+                        },
+                        "payloadId": null,
+                    },
+                })
+                .to_string(),
             );
+        });
+
+        let eth1_api = Arc::new(eth1_api(&server)?);
+        let (execution_service, execution_service_tx, _mutator_handle) =
+            execution_service(eth1_api)?;
+
+        // The retry would not be made before the end of the test.
+        let execution_service =
+            execution_service.with_syncing_payload_policy(SyncingPayloadPolicy::WaitForValid {
+                retry_delay: Duration::from_secs(3600),
+                max_retries: 1,
+            });
+
+        let (new_payload_tx, mut new_payload_rx) = oneshot::channel();
+        let (forkchoice_updated_tx, forkchoice_updated_rx) = oneshot::channel();
+
+        ExecutionServiceMessage::NotifyNewPayload {
+            beacon_block_root: H256::zero(),
+            payload: BellatrixExecutionPayload::default().into(),
+            params: None,
+            sender: Some(new_payload_tx),
+        }
+        .send(&execution_service_tx);
+
+        ExecutionServiceMessage::NotifyForkchoiceUpdated {
+            head_eth1_block_hash: ExecutionBlockHash::zero(),
+            safe_eth1_block_hash: ExecutionBlockHash::zero(),
+            finalized_eth1_block_hash: ExecutionBlockHash::zero(),
+            payload_attributes: Either::Left(Phase::Bellatrix),
+            sender: Some(forkchoice_updated_tx),
+        }
+        .send(&execution_service_tx);
+
+        let payload_id = select! {
+            result = execution_service.run().fuse() => bail!("execution service stopped: {result:?}"),
+            payload_id = forkchoice_updated_rx.fuse() => payload_id?,
+        };
+
+        assert!(payload_id.is_none());
+        assert!(new_payload_rx.try_recv()?.is_none());
+
+        syncing_mock.assert_hits(1);
+        forkchoice_updated_mock.assert_hits(1);
+
+        Ok(())
+    }
+
+    #[test_case(SyncingPayloadPolicy::Optimistic, PayloadValidationStatus::Syncing => None)]
+    #[test_case(
+        WAIT_FOR_VALID, PayloadValidationStatus::Syncing
+            => Some((Duration::from_millis(100), 10))
+    )]
+    #[test_case(WAIT_FOR_VALID, PayloadValidationStatus::Valid => None)]
+    #[test_case(
+        SyncingPayloadPolicy::WaitForValid {
+            retry_delay: Duration::from_millis(100),
+            max_retries: 0,
+        },
+        PayloadValidationStatus::Syncing
+            => None;
+        "no retries allowed"
+    )]
+    fn test_syncing_payload_policy_retries(
+        policy: SyncingPayloadPolicy,
+        status: PayloadValidationStatus,
+    ) -> Option<(Duration, usize)> {
+        policy.retries(&payload_status(status))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_syncing_response_is_retried_with_wait_for_valid_policy() -> Result<()> {
+        let retry_delay = Duration::from_millis(100);
+
+        let mut responses = VecDeque::from([
+            payload_status(PayloadValidationStatus::Syncing),
+            payload_status(PayloadValidationStatus::Valid),
+        ]);
+
+        let start = time::Instant::now();
+
+        let response = wait_for_valid(
+            payload_status(PayloadValidationStatus::Syncing),
+            retry_delay,
+            10,
+            || core::future::ready(Ok(responses.pop_front().expect("too many retries"))),
+        )
+        .await?;
+
+        assert_eq!(response.status, PayloadValidationStatus::Valid);
+        assert_eq!(start.elapsed(), retry_delay * 2);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wait_for_valid_policy_gives_up_after_max_retries() -> Result<()> {
+        let retry_delay = Duration::from_millis(10);
+        let mut retries = 0;
+
+        let start = time::Instant::now();
+
+        let response = wait_for_valid(
+            payload_status(PayloadValidationStatus::Syncing),
+            retry_delay,
+            2,
+            || {
+                retries += 1;
+                core::future::ready(Ok(payload_status(PayloadValidationStatus::Syncing)))
+            },
+        )
+        .await?;
+
+        assert_eq!(response.status, PayloadValidationStatus::Syncing);
+        assert_eq!(retries, 2);
+        assert_eq!(start.elapsed(), retry_delay * 2);
+
+        Ok(())
+    }
+
+    fn payload_status(status: PayloadValidationStatus) -> PayloadStatusV1 {
+        PayloadStatusV1 {
+            status,
+            latest_valid_hash: None,
+            validation_error: None,
         }
+    }
+
+    // The last element is the handle of the mutator thread. It must be kept alive.
+    fn execution_service(
+        eth1_api: Arc<Eth1Api>,
+    ) -> Result<(
+        ExecutionService<Minimal, ()>,
+        UnboundedSender<ExecutionServiceMessage<Minimal>>,
+        impl Sized,
+    )> {
+        let config = Arc::new(Config::minimal());
+        let (anchor_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let anchor_block = Arc::new(genesis::beacon_block(&anchor_state));
+        let (execution_service_tx, execution_service_rx) = mpsc::unbounded();
+
+        let execution_engine = Arc::new(Eth1ExecutionEngine::new(
+            config.clone_arc(),
+            eth1_api.clone_arc(),
+            execution_service_tx.clone(),
+        ));
+
+        let storage = Arc::new(Storage::new(
+            config.clone_arc(),
+            Database::in_memory(),
+            DEFAULT_ARCHIVAL_EPOCH_INTERVAL,
+            false,
+        ));
+
+        let (controller, mutator_handle) = Controller::new(
+            config,
+            StoreConfig::default(),
+            anchor_block.clone_arc(),
+            anchor_state,
+            Tick::block_proposal(&anchor_block),
+            execution_engine,
+            None,
+            futures::sink::drain(),
+            mpsc::unbounded().0,
+            futures::sink::drain(),
+            futures::sink::drain(),
+            futures::sink::drain(),
+            futures::sink::drain(),
+            futures::sink::drain(),
+            storage,
+            core::iter::empty(),
+        )?;
+
+        let execution_service = ExecutionService::new(eth1_api, controller, execution_service_rx);
+
+        Ok((execution_service, execution_service_tx, mutator_handle))
+    }
+
+    fn mock_new_payload<'server>(server: &'server MockServer, status: &str) -> Mock<'server> {
+        server.mock(|when, then| {
+            when.method(Method::POST)
+                .path("/")
+                .body_contains("engine_newPayloadV1");
+
+            then.status(200).body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {
+                        "status": status,
+                        "latestValidHash": null,
+                        "validationError": null,
+                    },
+                })
+                .to_string(),
+            );
+        })
+    }
 
-        Ok(response)
+    fn eth1_api(server: &MockServer) -> Result<Eth1Api> {
+        Ok(Eth1Api::new(
+            Config::mainnet().into(),
+            Client::new(),
+            Arc::default(),
+            vec![server.url("/").parse()?],
+            None,
+            None,
+        ))
     }
 }
@@ -1,17 +1,45 @@
 pub use crate::{
     auth::{Auth, Options as AuthOptions},
     deposit_event::DepositEvent,
-    eth1_api::Eth1Api,
+    eth1_api::{
+        DepositRangeFailure, EndpointHealth, EndpointSelector, EngineCall, EngineCallObserver,
+        EngineCallRecord, ErrorRecord, Eth1Api, InOrderEndpointSelector, NewPayloadCrossCheck,
+        PartialDepositEvents, SyncProgress,
+    },
     eth1_block::Eth1Block,
     eth1_execution_engine::Eth1ExecutionEngine,
-    execution_service::ExecutionService,
+    execution_service::{ExecutionService, SyncingPayloadPolicy},
     messages::{Eth1ApiToMetrics, Eth1ConnectionData, Eth1Metrics, ExecutionServiceMessage},
     misc::{ApiController, RealController},
 };
 
+#[cfg(feature = "optimism")]
+pub use crate::eth1_api::OptimismPayloadAttributes;
+
 mod auth;
 mod deposit_event;
-mod eth1_api;
+
+mod eth1_api {
+    pub use api::{Eth1Api, SyncProgress};
+    pub use deposits::{DepositRangeFailure, PartialDepositEvents};
+    pub use endpoints::{EndpointHealth, EndpointSelector, ErrorRecord, InOrderEndpointSelector};
+    pub use engine::{EngineCall, EngineCallObserver, EngineCallRecord, NewPayloadCrossCheck};
+
+    #[cfg(feature = "optimism")]
+    pub use engine::OptimismPayloadAttributes;
+
+    pub(crate) use engine::is_fatal_invalid_payload;
+
+    mod api;
+    mod deposits;
+    mod endpoints;
+    mod engine;
+    mod transport;
+
+    #[cfg(test)]
+    mod helpers;
+}
+
 mod eth1_block;
 mod eth1_execution_engine;
 mod execution_service;
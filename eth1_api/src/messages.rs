@@ -48,11 +48,3 @@ pub struct Eth1ConnectionData {
 pub enum Eth1ApiToMetrics {
     Eth1Connection(Eth1ConnectionData),
 }
-
-impl Eth1ApiToMetrics {
-    pub(crate) fn send(self, tx: &UnboundedSender<Self>) {
-        if tx.unbounded_send(self).is_err() {
-            debug!("send to metrics service failed because the receiver was dropped");
-        }
-    }
-}
@@ -43,7 +43,19 @@ pub trait ExecutionEngine<P: Preset> {
     ) -> Result<()>;
 
     /// [`get_pow_block`](https://github.com/ethereum/consensus-specs/blob/1bfefe301da592375e2e02f65849a96aadec1936/specs/bellatrix/fork-choice.md#get_pow_block)
-    fn pow_block(&self, block_hash: ExecutionBlockHash) -> Option<TimedPowBlock>;
+    fn pow_block(&self, block_hash: ExecutionBlockHash) -> PowBlockLookup;
+}
+
+/// Outcome of [`ExecutionEngine::pow_block`].
+#[derive(Clone, Copy)]
+pub enum PowBlockLookup {
+    Found(TimedPowBlock),
+    /// The block is not known or the request for it failed.
+    NotFound,
+    /// The execution client did not respond in time.
+    ///
+    /// Unlike missing blocks, this is never treated as grounds for optimistic validation.
+    TimedOut,
 }
 
 impl<P: Preset, E: ExecutionEngine<P>> ExecutionEngine<P> for &E {
@@ -80,7 +92,7 @@ impl<P: Preset, E: ExecutionEngine<P>> ExecutionEngine<P> for &E {
         (*self).notify_new_payload(beacon_block_root, payload, params, sender)
     }
 
-    fn pow_block(&self, block_hash: ExecutionBlockHash) -> Option<TimedPowBlock> {
+    fn pow_block(&self, block_hash: ExecutionBlockHash) -> PowBlockLookup {
         (*self).pow_block(block_hash)
     }
 }
@@ -120,7 +132,7 @@ impl<P: Preset, E: ExecutionEngine<P>> ExecutionEngine<P> for Arc<E> {
             .notify_new_payload(beacon_block_root, payload, params, sender)
     }
 
-    fn pow_block(&self, block_hash: ExecutionBlockHash) -> Option<TimedPowBlock> {
+    fn pow_block(&self, block_hash: ExecutionBlockHash) -> PowBlockLookup {
         self.as_ref().pow_block(block_hash)
     }
 }
@@ -165,7 +177,7 @@ impl<P: Preset, E: ExecutionEngine<P>> ExecutionEngine<P> for Mutex<E> {
             .notify_new_payload(beacon_block_root, payload, params, sender)
     }
 
-    fn pow_block(&self, block_hash: ExecutionBlockHash) -> Option<TimedPowBlock> {
+    fn pow_block(&self, block_hash: ExecutionBlockHash) -> PowBlockLookup {
         self.lock()
             .expect("execution engine mutex is poisoned")
             .pow_block(block_hash)
@@ -202,8 +214,8 @@ impl<P: Preset> ExecutionEngine<P> for NullExecutionEngine {
         Ok(())
     }
 
-    fn pow_block(&self, _block_hash: ExecutionBlockHash) -> Option<TimedPowBlock> {
-        None
+    fn pow_block(&self, _block_hash: ExecutionBlockHash) -> PowBlockLookup {
+        PowBlockLookup::NotFound
     }
 }
 
@@ -241,8 +253,11 @@ impl<P: Preset> ExecutionEngine<P> for MockExecutionEngine {
         Ok(())
     }
 
-    fn pow_block(&self, block_hash: ExecutionBlockHash) -> Option<TimedPowBlock> {
-        self.pow_blocks.get(&block_hash).copied()
+    fn pow_block(&self, block_hash: ExecutionBlockHash) -> PowBlockLookup {
+        self.pow_blocks
+            .get(&block_hash)
+            .copied()
+            .map_or(PowBlockLookup::NotFound, PowBlockLookup::Found)
     }
 }
 
@@ -1,11 +1,12 @@
 pub use crate::{
-    execution_engine::{ExecutionEngine, MockExecutionEngine, NullExecutionEngine},
+    execution_engine::{ExecutionEngine, MockExecutionEngine, NullExecutionEngine, PowBlockLookup},
     types::{
-        EngineGetPayloadV1Response, EngineGetPayloadV2Response, EngineGetPayloadV3Response,
-        ExecutionPayloadV1, ExecutionPayloadV2, ExecutionPayloadV3, ForkChoiceStateV1,
-        ForkChoiceUpdatedResponse, PayloadAttributes, PayloadAttributesV1, PayloadAttributesV2,
-        PayloadAttributesV3, PayloadId, PayloadStatusV1, PayloadStatusWithBlockHash,
-        PayloadValidationStatus,
+        EngineGetPayloadV1Response, EngineGetPayloadV2BellatrixResponse,
+        EngineGetPayloadV2Response, EngineGetPayloadV3Response, ExecutionPayloadV1,
+        ExecutionPayloadV2, ExecutionPayloadV3, ForkChoiceStateV1, ForkChoiceUpdatedResponse,
+        PayloadAttributes, PayloadAttributesV1, PayloadAttributesV2, PayloadAttributesV3,
+        PayloadId, PayloadStatusV1, PayloadStatusWithBlockHash, PayloadValidationStatus,
+        TransitionConfigurationV1, WithdrawalV1,
     },
 };
 
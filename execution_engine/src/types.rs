@@ -1,13 +1,16 @@
 use std::sync::Arc;
 
+use anyhow::{bail, Result};
 use derive_more::From;
 use ethereum_types::H64;
 use serde::{Deserialize, Serialize};
 use ssz::{ByteList, ByteVector, ContiguousList};
+use strum::AsRefStr;
+use thiserror::Error;
 use types::{
     bellatrix::{
         containers::ExecutionPayload as BellatrixExecutionPayload,
-        primitives::{Gas, Transaction, Wei},
+        primitives::{Difficulty, Gas, Transaction, Wei},
     },
     capella::{
         containers::{ExecutionPayload as CapellaExecutionPayload, Withdrawal},
@@ -363,6 +366,9 @@ impl<P: Preset> From<ExecutionPayloadV3<P>> for DenebExecutionPayload<P> {
 #[derive(Deserialize, Serialize)]
 #[serde(bound = "", rename_all = "camelCase")]
 pub struct BlobsBundleV1<P: Preset> {
+    // Early versions of the blob extension called commitments `kzgs`.
+    // Some execution clients still used that name after it was changed.
+    #[serde(alias = "kzgs")]
     pub commitments: ContiguousList<KzgCommitment, P::MaxBlobCommitmentsPerBlock>,
     pub proofs: ContiguousList<KzgProof, P::MaxBlobsPerBlock>,
     pub blobs: ContiguousList<Blob<P>, P::MaxBlobsPerBlock>,
@@ -379,6 +385,17 @@ pub struct ForkChoiceStateV1 {
     pub finalized_block_hash: ExecutionBlockHash,
 }
 
+/// [`TransitionConfigurationV1`](https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/paris.md#transitionconfigurationv1)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionConfigurationV1 {
+    #[serde(with = "serde_utils::prefixed_hex_quantity")]
+    pub terminal_total_difficulty: Difficulty,
+    pub terminal_block_hash: ExecutionBlockHash,
+    #[serde(with = "serde_utils::prefixed_hex_quantity")]
+    pub terminal_block_number: ExecutionBlockNumber,
+}
+
 /// [`PayloadAttributesV1`](https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/paris.md#payloadattributesv1)
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -421,10 +438,39 @@ impl<P: Preset> From<EngineGetPayloadV1Response<P>> for WithBlobsAndMev<Executio
     }
 }
 
+/// [`engine_getPayloadV2` response] specialized for Bellatrix.
+///
+/// Only used when an execution client does not support `engine_getPayloadV1`.
+///
+/// [`engine_getPayloadV2` response]: https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/shanghai.md#response-2
+#[derive(Deserialize)]
+#[serde(bound = "", rename_all = "camelCase")]
+pub struct EngineGetPayloadV2BellatrixResponse<P: Preset> {
+    pub execution_payload: ExecutionPayloadV1<P>,
+    #[serde(with = "serde_utils::prefixed_hex_quantity")]
+    pub block_value: Wei,
+}
+
+impl<P: Preset> From<EngineGetPayloadV2BellatrixResponse<P>>
+    for WithBlobsAndMev<ExecutionPayload<P>, P>
+{
+    fn from(response: EngineGetPayloadV2BellatrixResponse<P>) -> Self {
+        let EngineGetPayloadV2BellatrixResponse {
+            execution_payload,
+            block_value,
+        } = response;
+
+        let execution_payload = ExecutionPayload::Bellatrix(execution_payload.into());
+
+        Self::with_default(execution_payload).with_mev(block_value)
+    }
+}
+
 /// [`engine_getPayloadV2` response] specialized for Capella.
 ///
-/// [`execution_payload`] could also contain an [`ExecutionPayloadV1`],
-/// but we never call `engine_getPayloadV2` with Bellatrix payload IDs.
+/// [`execution_payload`] could also contain an [`ExecutionPayloadV1`].
+/// Responses to requests with Bellatrix payload IDs are deserialized as
+/// [`EngineGetPayloadV2BellatrixResponse`] instead.
 ///
 /// [`engine_getPayloadV2` response]: https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/shanghai.md#response-2
 /// [`execution_payload`]:            #structfield.execution_payload
@@ -459,7 +505,11 @@ pub struct EngineGetPayloadV3Response<P: Preset> {
     pub execution_payload: ExecutionPayloadV3<P>,
     #[serde(with = "serde_utils::prefixed_hex_quantity")]
     pub block_value: Wei,
+    // Some execution clients used the field name from the Rust structs they were generated from.
+    #[serde(alias = "blobs_bundle")]
     pub blobs_bundle: BlobsBundleV1<P>,
+    // Absent in responses from execution clients that predate the final Cancun specification.
+    #[serde(default)]
     pub should_override_builder: bool,
 }
 
@@ -507,6 +557,24 @@ impl<P: Preset> PayloadAttributes<P> {
             Self::Deneb(_) => Phase::Deneb,
         }
     }
+
+    #[must_use]
+    pub const fn timestamp(&self) -> UnixSeconds {
+        match self {
+            Self::Bellatrix(attributes) => attributes.timestamp,
+            Self::Capella(attributes) => attributes.timestamp,
+            Self::Deneb(attributes) => attributes.timestamp,
+        }
+    }
+
+    #[must_use]
+    pub fn withdrawals(&self) -> Option<&[WithdrawalV1]> {
+        match self {
+            Self::Bellatrix(_) => None,
+            Self::Capella(attributes) => Some(&attributes.withdrawals),
+            Self::Deneb(attributes) => Some(&attributes.withdrawals),
+        }
+    }
 }
 
 /// [`PayloadStatusV1`](https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/paris.md#payloadstatusv1)
@@ -519,6 +587,19 @@ pub struct PayloadStatusV1 {
     pub validation_error: Option<String>,
 }
 
+impl PayloadStatusV1 {
+    /// Returns `true` if the execution client has not fully validated the payload.
+    ///
+    /// Blocks with such payloads can only be imported optimistically.
+    #[must_use]
+    pub const fn is_optimistic(&self) -> bool {
+        matches!(
+            self.status,
+            PayloadValidationStatus::Syncing | PayloadValidationStatus::Accepted,
+        )
+    }
+}
+
 /// [`WithdrawalV1`](https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/shanghai.md#withdrawalv1)
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -568,13 +649,16 @@ impl From<WithdrawalV1> for Withdrawal {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, AsRefStr)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 #[cfg_attr(test, derive(Serialize))]
 pub enum PayloadValidationStatus {
     Valid,
     Invalid,
     Syncing,
+    /// The payload is valid as far as the execution client can tell without executing it,
+    /// but it is not part of the canonical chain, so it has not been fully validated.
     Accepted,
     InvalidBlockHash,
 }
@@ -594,6 +678,11 @@ impl PayloadValidationStatus {
     pub const fn is_syncing(self) -> bool {
         matches!(self, Self::Syncing)
     }
+
+    #[must_use]
+    pub const fn is_accepted(self) -> bool {
+        matches!(self, Self::Accepted)
+    }
 }
 
 /// [`engine_forkchoiceUpdated` response](https://github.com/ethereum/execution-apis/blob/b7c5d3420e00648f456744d121ffbd929862924d/src/engine/paris.md#response-1)
@@ -603,13 +692,27 @@ pub struct ForkChoiceUpdatedResponse {
     pub payload_id: Option<PayloadId>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum PayloadId {
     Bellatrix(H64),
     Capella(H64),
     Deneb(H64),
 }
 
+impl PayloadId {
+    /// Wraps `id` in the variant for `phase`.
+    ///
+    /// Fails for phases before Bellatrix, which have no execution payloads.
+    pub fn for_phase(phase: Phase, id: H64) -> Result<Self> {
+        match phase {
+            Phase::Phase0 | Phase::Altair => bail!(Error::PhasePreBellatrix { phase }),
+            Phase::Bellatrix => Ok(Self::Bellatrix(id)),
+            Phase::Capella => Ok(Self::Capella(id)),
+            Phase::Deneb => Ok(Self::Deneb(id)),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct PayloadStatusWithBlockHash {
@@ -643,11 +746,17 @@ impl From<PayloadStatus> for PayloadStatusV1 {
     }
 }
 
+#[derive(Debug, Error)]
+enum Error {
+    #[error("{phase} phase has no payload IDs")]
+    PhasePreBellatrix { phase: Phase },
+}
+
 #[cfg(test)]
 mod tests {
-    use anyhow::Result;
     use hex_literal::hex;
     use serde_json::{json, Value};
+    use test_case::test_case;
     use types::{phase0::primitives::H160, preset::Mainnet};
 
     use super::*;
@@ -723,6 +832,50 @@ mod tests {
         Ok(())
     }
 
+    #[test_case("blobsBundle", "commitments", true)]
+    #[test_case("blobs_bundle", "commitments", true)]
+    #[test_case("blobsBundle", "kzgs", true)]
+    #[test_case("blobs_bundle", "kzgs", true)]
+    #[test_case("blobsBundle", "commitments", false)]
+    fn test_engine_get_payload_v3_response_with_blobs_bundle_variant(
+        bundle_key: &str,
+        commitments_key: &str,
+        with_should_override_builder: bool,
+    ) -> Result<()> {
+        let mut json = sample_deneb_response_json();
+        let response = json
+            .as_object_mut()
+            .expect("sample response should be an object");
+        let mut bundle = response
+            .remove("blobsBundle")
+            .expect("sample response should contain a blobs bundle");
+        let bundle_fields = bundle
+            .as_object_mut()
+            .expect("sample blobs bundle should be an object");
+
+        let commitments = bundle_fields
+            .remove("commitments")
+            .expect("sample blobs bundle should contain commitments");
+
+        bundle_fields.insert(commitments_key.to_owned(), commitments);
+        response.insert(bundle_key.to_owned(), bundle);
+
+        if !with_should_override_builder {
+            response.remove("shouldOverrideBuilder");
+        }
+
+        let response = serde_json::from_value::<EngineGetPayloadV3Response<Mainnet>>(json)?;
+
+        assert!(!response.should_override_builder);
+
+        let actual_payload = WithBlobsAndMev::from(response);
+        let expected_payload = sample_deneb_payload().map(Into::into);
+
+        assert_eq!(actual_payload, expected_payload);
+
+        Ok(())
+    }
+
     #[test]
     fn test_payload_status_v1_round_trip() -> Result<()> {
         let json = json!({
@@ -746,6 +899,15 @@ mod tests {
         Ok(())
     }
 
+    #[test_case(Phase::Phase0 => None)]
+    #[test_case(Phase::Altair => None)]
+    #[test_case(Phase::Bellatrix => Some(PayloadId::Bellatrix(H64::repeat_byte(1))))]
+    #[test_case(Phase::Capella => Some(PayloadId::Capella(H64::repeat_byte(1))))]
+    #[test_case(Phase::Deneb => Some(PayloadId::Deneb(H64::repeat_byte(1))))]
+    fn test_payload_id_for_phase(phase: Phase) -> Option<PayloadId> {
+        PayloadId::for_phase(phase, H64::repeat_byte(1)).ok()
+    }
+
     // JSON response from `geth` with an Eth1 block from the Kiln testnet.
     // Also available at <https://explorer.kiln.themerge.dev/block/55000>.
     // ```json
@@ -1,19 +1,29 @@
-use std::sync::Arc;
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+};
 
-use anyhow::Result;
-use derive_more::Constructor;
+use anyhow::{ensure, Result};
 use execution_engine::ExecutionEngine;
 use fork_choice_store::{
-    validate_merge_block, BlockAction, PartialBlockAction, StateCacheProcessor, Store,
+    validate_merge_block, BlockAction, ChainLink, PartialBlockAction, StateCacheProcessor, Store,
 };
 use helper_functions::{
     predicates,
     slot_report::{NullSlotReport, RealSlotReport, SlotReport, SyncAggregateRewards},
     verifier::Verifier,
 };
+use itertools::Itertools as _;
+use log::info;
+use parking_lot::Mutex;
+use prometheus_metrics::METRICS;
+use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
 use ssz::SszHash;
 use state_cache::StateWithRewards;
 use std_ext::ArcExt as _;
+use thiserror::Error;
+use tokio::sync::broadcast::{self, Receiver, Sender};
 use transition_functions::{
     combined,
     unphased::{ProcessSlots, StateRootPolicy},
@@ -21,27 +31,186 @@ use transition_functions::{
 use types::{
     combined::{BeaconBlock, BeaconState, BlindedBeaconBlock, SignedBeaconBlock},
     config::Config as ChainConfig,
-    nonstandard::{BlockRewards, Phase, SlashingKind},
-    phase0::primitives::H256,
+    nonstandard::{BlockRewards, GweiVec, Phase, SlashingKind},
+    phase0::primitives::{Gwei, Slot, H256},
     preset::Preset,
     traits::{BeaconBlock as _, BeaconState as _, SignedBeaconBlock as _},
 };
 
-#[derive(Constructor)]
+// The number of events a subscriber can fall behind by before it starts missing them.
+pub const PROCESSED_BLOCK_EVENT_CAPACITY: usize = 64;
+
+/// Sent to subscribers of [`BlockProcessor::subscribe`] after a block is processed by one of
+/// the `process_*_with_report` methods.
+///
+/// Blocks whose post-states are found in the state cache are not processed again.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ProcessedBlockEvent {
+    pub slot: Slot,
+    pub block_root: H256,
+    pub rewards: BlockRewards,
+}
+
+/// Sums of [`BlockRewards`] over multiple blocks, e.g., all blocks in an epoch.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct AggregateRewards {
+    pub blocks: u64,
+    pub rewards: BlockRewards,
+}
+
+impl AggregateRewards {
+    /// Adds the rewards of a single block to the sums.
+    ///
+    /// Fails without modifying `self` if any of the sums would overflow.
+    pub fn add(&mut self, block_rewards: BlockRewards) -> Result<()> {
+        let sum = |aggregate: Gwei, block: Gwei| {
+            aggregate.checked_add(block).ok_or(Error::RewardsOverflow)
+        };
+
+        let rewards = BlockRewards {
+            total: sum(self.rewards.total, block_rewards.total)?,
+            attestations: sum(self.rewards.attestations, block_rewards.attestations)?,
+            sync_aggregate: sum(self.rewards.sync_aggregate, block_rewards.sync_aggregate)?,
+            proposer_slashings: sum(
+                self.rewards.proposer_slashings,
+                block_rewards.proposer_slashings,
+            )?,
+            attester_slashings: sum(
+                self.rewards.attester_slashings,
+                block_rewards.attester_slashings,
+            )?,
+        };
+
+        *self = Self {
+            blocks: self.blocks + 1,
+            rewards,
+        };
+
+        Ok(())
+    }
+}
+
 pub struct BlockProcessor<P: Preset> {
     chain_config: Arc<ChainConfig>,
     state_cache: Arc<StateCacheProcessor<P>>,
+    highest_processed_slot: AtomicU64,
+    highest_processed_phase: Mutex<Option<Phase>>,
+    // See `BlockProcessor::with_state_root_trust_threshold`.
+    state_root_trust_threshold: Option<u64>,
+    verified_state_root_streak: AtomicU64,
+    processed_block_events: Sender<ProcessedBlockEvent>,
 }
 
 impl<P: Preset> BlockProcessor<P> {
+    #[must_use]
+    pub fn new(chain_config: Arc<ChainConfig>, state_cache: Arc<StateCacheProcessor<P>>) -> Self {
+        Self {
+            chain_config,
+            state_cache,
+            highest_processed_slot: AtomicU64::new(0),
+            highest_processed_phase: Mutex::new(None),
+            state_root_trust_threshold: None,
+            verified_state_root_streak: AtomicU64::new(0),
+            processed_block_events: broadcast::channel(PROCESSED_BLOCK_EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Makes [`BlockProcessor::perform_state_transition`] trust state roots in blocks after
+    /// `threshold` consecutive state roots have been verified successfully.
+    ///
+    /// This is unsafe in the sense that a block with an incorrect state root will be accepted
+    /// once the threshold is reached. It is meant only for replaying blocks from a trusted source
+    /// and must not be enabled for a `BlockProcessor` that handles blocks from the network.
+    /// Verification resumes after [`BlockProcessor::end_replay_batch`] is called or a state
+    /// transition fails.
+    #[must_use]
+    pub const fn with_state_root_trust_threshold(mut self, threshold: u64) -> Self {
+        self.state_root_trust_threshold = Some(threshold);
+        self
+    }
+
+    /// Returns the highest slot of all blocks successfully processed by this `BlockProcessor`.
+    ///
+    /// Blocks found in the state cache count as processed.
+    /// Blocks validated with [`BlockProcessor::validate_block_for_gossip`] do not.
+    #[must_use]
+    pub fn highest_processed_slot(&self) -> Slot {
+        self.highest_processed_slot.load(Ordering::Relaxed)
+    }
+
+    /// Returns the latest phase of all blocks successfully processed by this `BlockProcessor`.
+    ///
+    /// Blocks found in the state cache do not count.
+    /// The first block processed in a new phase is logged to mark the fork activation if its
+    /// parent is from an earlier phase.
+    #[must_use]
+    pub fn highest_processed_phase(&self) -> Option<Phase> {
+        *self.highest_processed_phase.lock()
+    }
+
+    /// Returns a receiver of [`ProcessedBlockEvent`]s for blocks successfully processed by this
+    /// `BlockProcessor`.
+    ///
+    /// Events are sent without waiting for subscribers. A subscriber that falls behind by more
+    /// than [`PROCESSED_BLOCK_EVENT_CAPACITY`] events misses the oldest ones.
+    #[must_use]
+    pub fn subscribe(&self) -> Receiver<ProcessedBlockEvent> {
+        self.processed_block_events.subscribe()
+    }
+
+    /// See [`StateCacheProcessor::eviction_rate`].
+    #[must_use]
+    pub fn state_cache_eviction_rate(&self) -> f64 {
+        self.state_cache.eviction_rate()
+    }
+
+    /// Resets the streak of verified state roots counted toward the threshold set with
+    /// [`BlockProcessor::with_state_root_trust_threshold`].
+    pub fn end_replay_batch(&self) {
+        self.verified_state_root_streak.store(0, Ordering::Relaxed);
+    }
+
+    /// Processes slots for the state of `block_root` up to `target_slot` and stores the result in
+    /// the state cache.
+    ///
+    /// This is meant to be called ahead of a scheduled proposal so that the state transition for
+    /// the proposed block does not have to process slots. States are only cached when the store
+    /// is forward synced. It is safe to call this concurrently with block processing.
+    pub fn prewarm_state(
+        &self,
+        store: &Store<P>,
+        block_root: H256,
+        target_slot: Slot,
+    ) -> Result<()> {
+        self.state_cache
+            .state_at_slot_quiet(store, block_root, target_slot)
+            .map(|_| ())
+    }
+
+    /// Like [`BlockProcessor::prewarm_state`], but for multiple upcoming proposal slots.
+    ///
+    /// Slots are processed in ascending order, so the state for each slot is computed from the
+    /// cached state for the slot before it rather than from the state of `block_root`.
+    /// States that are already cached are not recomputed.
+    pub fn prewarm_slots(&self, store: &Store<P>, block_root: H256, slots: &[Slot]) -> Result<()> {
+        for slot in slots.iter().copied().sorted_unstable().dedup() {
+            self.prewarm_state(store, block_root, slot)?;
+        }
+
+        Ok(())
+    }
+
     pub fn process_untrusted_block_with_report(
         &self,
         mut state: Arc<BeaconState<P>>,
         block: &BeaconBlock<P>,
         skip_randao_verification: bool,
     ) -> Result<StateWithRewards<P>> {
+        let block_root = block.hash_tree_root();
+
         self.state_cache
-            .get_or_insert_with(block.hash_tree_root(), block.slot(), false, || {
+            .get_or_insert_with(block_root, block.slot(), false, || {
+                let parent_phase = self.parent_phase(&state);
                 let mut slot_report = RealSlotReport::default();
 
                 combined::process_untrusted_block(
@@ -54,8 +223,49 @@ impl<P: Preset> BlockProcessor<P> {
 
                 let block_rewards = calculate_block_rewards(&slot_report);
 
+                self.record_processed_phase(block.slot(), parent_phase, block.phase());
+                count_processed_block(false);
+                self.send_processed_block_event(block_root, block.slot(), block_rewards);
+
                 Ok((state, Some(block_rewards)))
             })
+            .inspect(|_| self.record_processed_slot(block.slot()))
+    }
+
+    /// Like [`BlockProcessor::process_untrusted_block_with_report`], but passes signatures to
+    /// `verifier` instead of verifying them one block at a time.
+    ///
+    /// This lets callers verify signatures from many blocks in a single batch or skip
+    /// verification for blocks whose signatures have already been verified elsewhere.
+    pub fn process_block_with_verifier(
+        &self,
+        mut state: Arc<BeaconState<P>>,
+        block: &BeaconBlock<P>,
+        verifier: impl Verifier + Send,
+    ) -> Result<StateWithRewards<P>> {
+        let block_root = block.hash_tree_root();
+
+        self.state_cache
+            .get_or_insert_with(block_root, block.slot(), false, || {
+                let parent_phase = self.parent_phase(&state);
+                let mut slot_report = RealSlotReport::default();
+
+                combined::process_block(
+                    &self.chain_config,
+                    state.make_mut(),
+                    block,
+                    verifier,
+                    &mut slot_report,
+                )?;
+
+                let block_rewards = calculate_block_rewards(&slot_report);
+
+                self.record_processed_phase(block.slot(), parent_phase, block.phase());
+                count_processed_block(false);
+
+                Ok((state, Some(block_rewards)))
+            })
+            .inspect(|_| self.record_processed_slot(block.slot()))
     }
 
     pub fn process_trusted_block_with_report(
@@ -63,8 +273,11 @@ impl<P: Preset> BlockProcessor<P> {
         mut state: Arc<BeaconState<P>>,
         block: &BeaconBlock<P>,
     ) -> Result<StateWithRewards<P>> {
+        let block_root = block.hash_tree_root();
+
         self.state_cache
-            .get_or_insert_with(block.hash_tree_root(), block.slot(), false, || {
+            .get_or_insert_with(block_root, block.slot(), false, || {
+                let parent_phase = self.parent_phase(&state);
                 let mut slot_report = RealSlotReport::default();
 
                 combined::process_trusted_block(
@@ -76,8 +289,13 @@ impl<P: Preset> BlockProcessor<P> {
 
                 let block_rewards = calculate_block_rewards(&slot_report);
 
+                self.record_processed_phase(block.slot(), parent_phase, block.phase());
+                count_processed_block(false);
+                self.send_processed_block_event(block_root, block.slot(), block_rewards);
+
                 Ok((state, Some(block_rewards)))
             })
+            .inspect(|_| self.record_processed_slot(block.slot()))
     }
 
     pub fn process_untrusted_blinded_block_with_report(
@@ -86,8 +304,11 @@ impl<P: Preset> BlockProcessor<P> {
         block: &BlindedBeaconBlock<P>,
         skip_randao_verification: bool,
     ) -> Result<StateWithRewards<P>> {
+        let block_root = block.hash_tree_root();
+
         self.state_cache
-            .get_or_insert_with(block.hash_tree_root(), block.slot(), false, || {
+            .get_or_insert_with(block_root, block.slot(), false, || {
+                let parent_phase = self.parent_phase(&state);
                 let mut slot_report = RealSlotReport::default();
 
                 combined::process_untrusted_blinded_block(
@@ -100,8 +321,13 @@ impl<P: Preset> BlockProcessor<P> {
 
                 let block_rewards = calculate_block_rewards(&slot_report);
 
+                self.record_processed_phase(block.slot(), parent_phase, block.phase());
+                count_processed_block(true);
+                self.send_processed_block_event(block_root, block.slot(), block_rewards);
+
                 Ok((state, Some(block_rewards)))
             })
+            .inspect(|_| self.record_processed_slot(block.slot()))
     }
 
     pub fn process_trusted_blinded_block_with_report(
@@ -109,8 +335,11 @@ impl<P: Preset> BlockProcessor<P> {
         mut state: Arc<BeaconState<P>>,
         block: &BlindedBeaconBlock<P>,
     ) -> Result<StateWithRewards<P>> {
+        let block_root = block.hash_tree_root();
+
         self.state_cache
-            .get_or_insert_with(block.hash_tree_root(), block.slot(), false, || {
+            .get_or_insert_with(block_root, block.slot(), false, || {
+                let parent_phase = self.parent_phase(&state);
                 let mut slot_report = RealSlotReport::default();
 
                 combined::process_trusted_blinded_block(
@@ -122,8 +351,56 @@ impl<P: Preset> BlockProcessor<P> {
 
                 let block_rewards = calculate_block_rewards(&slot_report);
 
+                self.record_processed_phase(block.slot(), parent_phase, block.phase());
+                count_processed_block(true);
+                self.send_processed_block_event(block_root, block.slot(), block_rewards);
+
                 Ok((state, Some(block_rewards)))
             })
+            .inspect(|_| self.record_processed_slot(block.slot()))
+    }
+
+    /// Applies `blocks` to `state` in order with [`BlockProcessor::process_trusted_block_with_report`]
+    /// and sums up their rewards.
+    ///
+    /// Slots are processed as needed, so the blocks may be from different slots.
+    pub fn process_trusted_blocks_with_aggregate_rewards(
+        &self,
+        mut state: Arc<BeaconState<P>>,
+        blocks: &[BeaconBlock<P>],
+    ) -> Result<(Arc<BeaconState<P>>, AggregateRewards)> {
+        let mut aggregate_rewards = AggregateRewards::default();
+
+        for block in blocks {
+            if state.slot() < block.slot() {
+                combined::process_slots(&self.chain_config, state.make_mut(), block.slot())?;
+            }
+
+            let (post_state, block_rewards) =
+                self.process_trusted_block_with_report(state, block)?;
+
+            aggregate_rewards.add(block_rewards.unwrap_or_default())?;
+            state = post_state;
+        }
+
+        Ok((state, aggregate_rewards))
+    }
+
+    /// Applies only the attestations in `block` to `state` and returns the resulting proposer
+    /// rewards, one for each attestation.
+    ///
+    /// This is much cheaper than [`BlockProcessor::process_untrusted_block_with_report`] but does
+    /// not validate the attestations. `state` must already be at the slot of `block`.
+    pub fn attestation_rewards(
+        &self,
+        mut state: Arc<BeaconState<P>>,
+        block: &BeaconBlock<P>,
+    ) -> Result<GweiVec> {
+        let mut slot_report = RealSlotReport::default();
+
+        combined::apply_block_attestations(state.make_mut(), block, &mut slot_report)?;
+
+        Ok(slot_report.attestation_rewards)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -140,6 +417,10 @@ impl<P: Preset> BlockProcessor<P> {
     ) -> Result<Arc<BeaconState<P>>> {
         self.state_cache
             .get_or_insert_with(block_root, block.message().slot(), true, || {
+                let parent_phase = self.parent_phase(&state);
+                let state_root_policy = self.effective_state_root_policy(state_root_policy);
+                let verifying = matches!(state_root_policy, StateRootPolicy::Verify);
+
                 combined::custom_state_transition(
                     &self.chain_config,
                     state.make_mut(),
@@ -149,36 +430,161 @@ impl<P: Preset> BlockProcessor<P> {
                     execution_engine,
                     verifier,
                     slot_report,
-                )?;
+                )
+                .inspect_err(|_| self.end_replay_batch())?;
+
+                if verifying {
+                    self.verified_state_root_streak
+                        .fetch_add(1, Ordering::Relaxed);
+                }
+
+                self.record_processed_phase(block.message().slot(), parent_phase, block.phase());
 
                 Ok((state, None))
             })
+            .inspect(|_| self.record_processed_slot(block.message().slot()))
             .map(|(state, _)| state)
     }
 
+    /// Applies `block` to the state with root `pre_state_root` in `store`.
+    ///
+    /// Meant for replaying blocks against historical states when debugging, e.g. to reproduce
+    /// how a block on another fork was processed. States of blocks on all unfinalized forks can be
+    /// found, as can states of the parent of `block` with empty slots processed that are in the
+    /// state cache. Unlike [`BlockProcessor::perform_state_transition`], this does not store the
+    /// resulting state in the state cache because it may not be the one on the canonical chain.
+    pub fn replay_block(
+        &self,
+        store: &Store<P>,
+        pre_state_root: H256,
+        block: &SignedBeaconBlock<P>,
+        execution_engine: impl ExecutionEngine<P> + Send,
+        verifier: impl Verifier + Send,
+    ) -> Result<Arc<BeaconState<P>>> {
+        let mut state =
+            self.replay_pre_state(store, pre_state_root, block)
+                .ok_or(Error::StateNotFound {
+                    state_root: pre_state_root,
+                })?;
+
+        let actual = state.hash_tree_root();
+
+        ensure!(
+            actual == pre_state_root,
+            Error::StateRootMismatch {
+                expected: pre_state_root,
+                actual,
+            },
+        );
+
+        combined::custom_state_transition(
+            &self.chain_config,
+            state.make_mut(),
+            block,
+            ProcessSlots::IfNeeded,
+            StateRootPolicy::Verify,
+            execution_engine,
+            verifier,
+            NullSlotReport,
+        )?;
+
+        Ok(state)
+    }
+
+    // `Store::state_by_state_root` only searches the canonical chain.
+    // The state cache is keyed by block root, so only states of the parent of `block` are checked.
+    fn replay_pre_state(
+        &self,
+        store: &Store<P>,
+        pre_state_root: H256,
+        block: &SignedBeaconBlock<P>,
+    ) -> Option<Arc<BeaconState<P>>> {
+        if let Some(with_status) = store.state_by_state_root(pre_state_root) {
+            return Some(with_status.value);
+        }
+
+        let non_canonical_chain_link = store
+            .unfinalized()
+            .values()
+            .flatten()
+            .map(|unfinalized_block| &unfinalized_block.chain_link)
+            .find(|chain_link| chain_link.block.message().state_root() == pre_state_root);
+
+        if let Some(chain_link) = non_canonical_chain_link {
+            return Some(chain_link.state(store));
+        }
+
+        self.state_cache
+            .before_or_at_slot(store, block.message().parent_root(), block.message().slot())
+            .filter(|state| state.hash_tree_root() == pre_state_root)
+    }
+
     pub fn validate_block_for_gossip(
         &self,
         store: &Store<P>,
         block: &Arc<SignedBeaconBlock<P>>,
     ) -> Result<Option<BlockAction<P>>> {
         store.validate_block_for_gossip(block, |parent| {
+            let state = self.gossip_pre_state(store, parent, block.message().slot())?;
+
+            combined::process_block_for_gossip(&self.chain_config, &state, block)?;
+
+            Ok(None)
+        })
+    }
+
+    /// Validates `blocks` like [`BlockProcessor::validate_block_for_gossip`] and returns the
+    /// results in the same order.
+    ///
+    /// Blocks with the same parent and slot share a single pre-state. This avoids processing the
+    /// same empty slots repeatedly when many blocks for a slot arrive at once.
+    /// Blocks are validated in parallel on the global Rayon thread pool because validation for
+    /// gossip does not modify `store` or the state cache.
+    pub fn validate_blocks_for_gossip(
+        &self,
+        store: &Store<P>,
+        blocks: &[Arc<SignedBeaconBlock<P>>],
+    ) -> Vec<Result<Option<BlockAction<P>>>> {
+        let mut pre_states = HashMap::new();
+
+        for block in blocks {
             let block_slot = block.message().slot();
+            let parent_root = block.message().parent_root();
 
-            // > Make a copy of the state to avoid mutability issues
-            let mut state = self
-                .state_cache
-                .before_or_at_slot(store, parent.block_root, block_slot)
-                .unwrap_or_else(|| parent.state(store));
+            // Blocks from future slots are delayed without being validated.
+            // Preparing states for them could require processing an arbitrary number of slots.
+            if store.slot() < block_slot {
+                continue;
+            }
+
+            let Some(parent) = store.chain_link(parent_root) else {
+                continue;
+            };
 
-            // > Process slots (including those with no blocks) since block
-            if state.slot() < block_slot {
-                combined::process_slots(&self.chain_config, state.make_mut(), block_slot)?;
+            if let Entry::Vacant(vacant) = pre_states.entry((parent_root, block_slot)) {
+                // Errors are reproduced when the affected blocks are validated individually.
+                if let Ok(state) = self.gossip_pre_state(store, parent, block_slot) {
+                    vacant.insert(state);
+                }
             }
+        }
 
-            combined::process_block_for_gossip(&self.chain_config, &state, block)?;
+        let validate = |block: &Arc<SignedBeaconBlock<P>>| {
+            store.validate_block_for_gossip(block, |parent| {
+                let block_slot = block.message().slot();
 
-            Ok(None)
-        })
+                let state = match pre_states.get(&(parent.block_root, block_slot)) {
+                    Some(state) => state.clone_arc(),
+                    None => self.gossip_pre_state(store, parent, block_slot)?,
+                };
+
+                combined::process_block_for_gossip(&self.chain_config, &state, block)?;
+
+                Ok(None)
+            })
+        };
+
+        blocks.par_iter().map(validate).collect()
     }
 
     pub fn validate_block<E: ExecutionEngine<P> + Send>(
@@ -188,6 +594,67 @@ impl<P: Preset> BlockProcessor<P> {
         state_root_policy: StateRootPolicy,
         execution_engine: E,
         verifier: impl Verifier + Send,
+    ) -> Result<BlockAction<P>> {
+        self.validate_block_with_state_transition(
+            store,
+            block,
+            execution_engine,
+            |state, block_root, execution_engine| {
+                self.perform_state_transition(
+                    state,
+                    block,
+                    block_root,
+                    ProcessSlots::IfNeeded,
+                    state_root_policy,
+                    execution_engine,
+                    verifier,
+                    NullSlotReport,
+                )
+            },
+        )
+    }
+
+    /// Like [`BlockProcessor::validate_block`], but does not store the post-state in the state
+    /// cache.
+    ///
+    /// Meant for speculative validation, e.g. when evaluating a competing fork.
+    /// Blocks validated this way are not counted as processed and do not count toward the
+    /// threshold set by [`BlockProcessor::with_state_root_trust_threshold`].
+    pub fn validate_block_ephemeral<E: ExecutionEngine<P> + Send>(
+        &self,
+        store: &Store<P>,
+        block: &Arc<SignedBeaconBlock<P>>,
+        state_root_policy: StateRootPolicy,
+        execution_engine: E,
+        verifier: impl Verifier + Send,
+    ) -> Result<BlockAction<P>> {
+        self.validate_block_with_state_transition(
+            store,
+            block,
+            execution_engine,
+            |mut state, _, execution_engine| {
+                combined::custom_state_transition(
+                    &self.chain_config,
+                    state.make_mut(),
+                    block,
+                    ProcessSlots::IfNeeded,
+                    state_root_policy,
+                    execution_engine,
+                    verifier,
+                    NullSlotReport,
+                )?;
+
+                Ok(state)
+            },
+        )
+    }
+
+    fn validate_block_with_state_transition<E: ExecutionEngine<P> + Send>(
+        &self,
+        store: &Store<P>,
+        block: &Arc<SignedBeaconBlock<P>>,
+        execution_engine: E,
+        state_transition: impl FnOnce(Arc<BeaconState<P>>, H256, E) -> Result<Arc<BeaconState<P>>>,
     ) -> Result<BlockAction<P>> {
         store.validate_block_with_custom_state_transition(block, |block_root, parent| {
             // > Make a copy of the state to avoid mutability issues
@@ -221,20 +688,114 @@ impl<P: Preset> BlockProcessor<P> {
                 }
             }
 
-            let state = self.perform_state_transition(
-                state,
-                block,
-                block_root,
-                ProcessSlots::IfNeeded,
-                state_root_policy,
-                execution_engine,
-                verifier,
-                NullSlotReport,
-            )?;
+            let state = state_transition(state, block_root, execution_engine)?;
 
             Ok((state, None))
         })
     }
+
+    fn gossip_pre_state(
+        &self,
+        store: &Store<P>,
+        parent: &ChainLink<P>,
+        block_slot: Slot,
+    ) -> Result<Arc<BeaconState<P>>> {
+        // > Make a copy of the state to avoid mutability issues
+        let mut state = self
+            .state_cache
+            .before_or_at_slot(store, parent.block_root, block_slot)
+            .unwrap_or_else(|| parent.state(store));
+
+        // > Process slots (including those with no blocks) since block
+        if state.slot() < block_slot {
+            combined::process_slots(&self.chain_config, state.make_mut(), block_slot)?;
+        }
+
+        Ok(state)
+    }
+
+    fn effective_state_root_policy(&self, requested: StateRootPolicy) -> StateRootPolicy {
+        let Some(threshold) = self.state_root_trust_threshold else {
+            return requested;
+        };
+
+        match requested {
+            StateRootPolicy::Verify
+                if self.verified_state_root_streak.load(Ordering::Relaxed) >= threshold =>
+            {
+                StateRootPolicy::Trust
+            }
+            policy => policy,
+        }
+    }
+
+    fn record_processed_slot(&self, slot: Slot) {
+        self.highest_processed_slot
+            .fetch_max(slot, Ordering::Relaxed);
+    }
+
+    // Called only when a block is actually processed, not when its post-state is found in the
+    // state cache. Otherwise subscribers would get an event every time the rewards are looked up.
+    fn send_processed_block_event(&self, block_root: H256, slot: Slot, rewards: BlockRewards) {
+        // Sending only fails if there are no subscribers.
+        self.processed_block_events
+            .send(ProcessedBlockEvent {
+                slot,
+                block_root,
+                rewards,
+            })
+            .ok();
+    }
+
+    // The parent of the block being applied to `state` is the one in `state.latest_block_header`.
+    // The phase of `state` itself may be later if slots have already been processed.
+    fn parent_phase(&self, state: &BeaconState<P>) -> Phase {
+        self.chain_config
+            .phase_at_slot::<P>(state.latest_block_header().slot)
+    }
+
+    // Returns `true` if `phase` is later than `parent_phase` and the phases of all blocks
+    // processed before. Blocks on different forks may cross the same boundary concurrently.
+    // The mutex ensures the transition is only reported for one of them.
+    fn record_processed_phase(&self, slot: Slot, parent_phase: Phase, phase: Phase) -> bool {
+        let mut highest_processed_phase = self.highest_processed_phase.lock();
+
+        if highest_processed_phase.is_some_and(|highest| highest >= phase) {
+            return false;
+        }
+
+        *highest_processed_phase = Some(phase);
+
+        drop(highest_processed_phase);
+
+        if parent_phase >= phase {
+            return false;
+        }
+
+        info!("processed first {phase} block at slot {slot} (previous phase: {parent_phase})");
+
+        if let Some(metrics) = METRICS.get() {
+            metrics
+                .phase_transitions_count
+                .with_label_values(&[phase.as_ref()])
+                .inc();
+        }
+
+        true
+    }
+}
+
+// A low share of blinded blocks may indicate problems with builder or relay connectivity.
+// Only blocks processed by the `process_*` methods are counted. Blocks from the network are
+// processed by `BlockProcessor::perform_state_transition` and would skew the ratio.
+fn count_processed_block(blinded: bool) {
+    if let Some(metrics) = METRICS.get() {
+        if blinded {
+            metrics.blinded_blocks_processed.inc();
+        } else {
+            metrics.full_blocks_processed.inc();
+        }
+    }
 }
 
 fn calculate_block_rewards(slot_report: &RealSlotReport) -> BlockRewards {
@@ -261,3 +822,823 @@ fn calculate_block_rewards(slot_report: &RealSlotReport) -> BlockRewards {
         attester_slashings,
     }
 }
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error("block rewards overflowed")]
+    RewardsOverflow,
+    #[error("state with root {state_root:?} not found")]
+    StateNotFound { state_root: H256 },
+    #[error("state found for root {expected:?} has root {actual:?}")]
+    StateRootMismatch { expected: H256, actual: H256 },
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use anyhow::bail;
+    use bls::{CachedPublicKey, PublicKey, SignatureBytes};
+    use clock::Tick;
+    use execution_engine::NullExecutionEngine;
+    use fork_choice_store::StoreConfig;
+    use helper_functions::{
+        error::SignatureKind,
+        verifier::{NullVerifier, Triple, VerifierOption},
+    };
+    use prometheus_metrics::Metrics;
+    use types::{config::Config, preset::Minimal};
+
+    use super::*;
+
+    #[test]
+    fn highest_processed_slot_is_retained_when_blocks_are_processed_out_of_order() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let (block_1, _) =
+            factory::empty_block(&config, genesis_state.clone_arc(), 1, H256::zero())?;
+        let (block_2, _) =
+            factory::empty_block(&config, genesis_state.clone_arc(), 2, H256::zero())?;
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache);
+
+        assert_eq!(block_processor.highest_processed_slot(), 0);
+
+        for block in [block_2, block_1] {
+            block_processor.perform_state_transition(
+                genesis_state.clone_arc(),
+                &block,
+                block.message().hash_tree_root(),
+                ProcessSlots::IfNeeded,
+                StateRootPolicy::Verify,
+                NullExecutionEngine,
+                NullVerifier,
+                NullSlotReport,
+            )?;
+        }
+
+        assert_eq!(block_processor.highest_processed_slot(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn subscribers_receive_events_for_processed_blocks() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let (block, _) = factory::empty_block(&config, genesis_state.clone_arc(), 1, H256::zero())?;
+        let (block, _) = block.as_ref().clone().split();
+        let (signed_block, _) =
+            factory::empty_block(&config, genesis_state.clone_arc(), 2, H256::zero())?;
+
+        let mut state = genesis_state.clone_arc();
+        combined::process_slots(&config, state.make_mut(), block.slot())?;
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache);
+        let mut receiver = block_processor.subscribe();
+
+        let (_, rewards) =
+            block_processor.process_trusted_block_with_report(state.clone_arc(), &block)?;
+
+        let rewards = rewards.expect("block rewards should be computed");
+
+        assert_eq!(
+            receiver.try_recv()?,
+            ProcessedBlockEvent {
+                slot: 1,
+                block_root: block.hash_tree_root(),
+                rewards,
+            },
+        );
+
+        // Looking up the post-state again should not produce another event.
+        block_processor.process_trusted_block_with_report(state, &block)?;
+
+        // Neither should processing without calculating rewards.
+        block_processor.perform_state_transition(
+            genesis_state,
+            &signed_block,
+            signed_block.message().hash_tree_root(),
+            ProcessSlots::IfNeeded,
+            StateRootPolicy::Verify,
+            NullExecutionEngine,
+            NullVerifier,
+            NullSlotReport,
+        )?;
+
+        assert!(receiver.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn state_roots_are_trusted_after_threshold_is_reached() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor =
+            BlockProcessor::new(config.clone_arc(), state_cache).with_state_root_trust_threshold(2);
+
+        for slot in 1..=2 {
+            assert!(matches!(
+                block_processor.effective_state_root_policy(StateRootPolicy::Verify),
+                StateRootPolicy::Verify,
+            ));
+
+            let (block, _) =
+                factory::empty_block(&config, genesis_state.clone_arc(), slot, H256::zero())?;
+
+            block_processor.perform_state_transition(
+                genesis_state.clone_arc(),
+                &block,
+                block.message().hash_tree_root(),
+                ProcessSlots::IfNeeded,
+                StateRootPolicy::Verify,
+                NullExecutionEngine,
+                NullVerifier,
+                NullSlotReport,
+            )?;
+        }
+
+        assert!(matches!(
+            block_processor.effective_state_root_policy(StateRootPolicy::Verify),
+            StateRootPolicy::Trust,
+        ));
+
+        block_processor.end_replay_batch();
+
+        assert!(matches!(
+            block_processor.effective_state_root_policy(StateRootPolicy::Verify),
+            StateRootPolicy::Verify,
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn blinded_and_full_blocks_are_counted_separately() -> Result<()> {
+        let metrics = METRICS.get_or_init(|| {
+            Arc::new(Metrics::new().expect("metrics should be created successfully"))
+        });
+
+        let config = Arc::new(Config::minimal().start_and_stay_in(Phase::Bellatrix));
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let (block, _) = factory::empty_block(&config, genesis_state.clone_arc(), 1, H256::zero())?;
+        let (block, _) = block.as_ref().clone().split();
+
+        let BeaconBlock::Bellatrix(bellatrix_block) = block.clone() else {
+            bail!("block should be from Bellatrix");
+        };
+
+        let header = (&bellatrix_block.body.execution_payload).into();
+        let blinded_block = bellatrix_block.with_execution_payload_header(header).into();
+
+        let mut state = genesis_state;
+        combined::process_slots(&config, state.make_mut(), block.slot())?;
+
+        // The blinded block has the same root as the full one.
+        // Separate state caches are needed to process both of them.
+        let new_block_processor = || {
+            let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+            BlockProcessor::new(config.clone_arc(), state_cache)
+        };
+
+        // Other tests may process blocks concurrently, so only check lower bounds.
+        let blinded_before = metrics.blinded_blocks_processed.get();
+        let full_before = metrics.full_blocks_processed.get();
+
+        new_block_processor().process_trusted_block_with_report(state.clone_arc(), &block)?;
+
+        assert!(metrics.full_blocks_processed.get() > full_before);
+
+        new_block_processor().process_trusted_blinded_block_with_report(state, &blinded_block)?;
+
+        assert!(metrics.blinded_blocks_processed.get() > blinded_before);
+
+        Ok(())
+    }
+
+    #[test]
+    fn process_block_with_verifier_passes_signatures_to_verifier() -> Result<()> {
+        #[derive(Default)]
+        struct CountingVerifier {
+            signatures: usize,
+        }
+
+        impl Verifier for CountingVerifier {
+            const IS_NULL: bool = false;
+
+            fn reserve(&mut self, _additional: usize) {}
+
+            fn verify_singular(
+                &mut self,
+                _message: H256,
+                _signature_bytes: SignatureBytes,
+                _cached_public_key: &CachedPublicKey,
+                _signature_kind: SignatureKind,
+            ) -> Result<()> {
+                self.signatures += 1;
+                Ok(())
+            }
+
+            fn verify_aggregate<'keys>(
+                &mut self,
+                _message: H256,
+                _signature_bytes: SignatureBytes,
+                _public_keys: impl IntoIterator<
+                    IntoIter = impl Iterator<Item = &'keys PublicKey> + Send,
+                >,
+                _signature_kind: SignatureKind,
+            ) -> Result<()> {
+                self.signatures += 1;
+                Ok(())
+            }
+
+            fn extend(
+                &mut self,
+                triples: impl IntoIterator<Item = Triple>,
+                _signature_kind: SignatureKind,
+            ) -> Result<()> {
+                self.signatures += triples.into_iter().count();
+                Ok(())
+            }
+
+            fn finish(&self) -> Result<()> {
+                Ok(())
+            }
+
+            fn has_option(&self, _option: VerifierOption) -> bool {
+                false
+            }
+        }
+
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let (block, _) = factory::empty_block(&config, genesis_state.clone_arc(), 1, H256::zero())?;
+        let (block, _) = block.as_ref().clone().split();
+
+        let mut state = genesis_state;
+        combined::process_slots(&config, state.make_mut(), block.slot())?;
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache);
+        let mut verifier = CountingVerifier::default();
+
+        block_processor.process_block_with_verifier(state, &block, &mut verifier)?;
+
+        // An empty block only contains the RANDAO reveal.
+        // The block signature is not part of block processing.
+        assert_eq!(verifier.signatures, 1);
+        assert_eq!(block_processor.highest_processed_slot(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn state_cache_wait_and_compute_times_are_recorded_separately() -> Result<()> {
+        let metrics = METRICS.get_or_init(|| {
+            Arc::new(Metrics::new().expect("metrics should be created successfully"))
+        });
+
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let (block, _) = factory::empty_block(&config, genesis_state.clone_arc(), 1, H256::zero())?;
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache);
+
+        // Other tests may use the state cache concurrently, so only check lower bounds.
+        let waits_before = metrics.state_cache_wait_times.get_sample_count();
+        let computations_before = metrics.state_cache_compute_times.get_sample_count();
+
+        block_processor.perform_state_transition(
+            genesis_state,
+            &block,
+            block.message().hash_tree_root(),
+            ProcessSlots::IfNeeded,
+            StateRootPolicy::Verify,
+            NullExecutionEngine,
+            NullVerifier,
+            NullSlotReport,
+        )?;
+
+        assert!(metrics.state_cache_wait_times.get_sample_count() > waits_before);
+        assert!(metrics.state_cache_compute_times.get_sample_count() > computations_before);
+
+        Ok(())
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn state_cache_eviction_rate_counts_pruned_states() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache.clone_arc());
+
+        assert_eq!(block_processor.state_cache_eviction_rate(), 0.0);
+
+        for byte in 1..=10 {
+            state_cache.get_or_insert_with(H256::repeat_byte(byte), 0, true, || {
+                Ok((genesis_state.clone_arc(), None))
+            })?;
+        }
+
+        state_cache.prune(1)?;
+
+        assert_eq!(state_cache.len()?, 0);
+
+        // 10 states evicted within the 32 minute window.
+        assert_eq!(block_processor.state_cache_eviction_rate(), 0.3125);
+
+        Ok(())
+    }
+
+    #[test]
+    fn prewarm_state_makes_later_lookups_cache_hits() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let genesis_block = Arc::new(genesis::beacon_block(&genesis_state));
+        let genesis_block_root = genesis_block.message().hash_tree_root();
+
+        let store = Store::new(
+            config.clone_arc(),
+            StoreConfig::default(),
+            genesis_block,
+            genesis_state,
+            true,
+        );
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache.clone_arc());
+
+        block_processor.prewarm_state(&store, genesis_block_root, 3)?;
+
+        let (state, _) = state_cache.get_or_insert_with(genesis_block_root, 3, true, || {
+            bail!("state should have been prewarmed")
+        })?;
+
+        assert_eq!(state.slot(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn prewarm_slots_makes_later_lookups_cache_hits_for_all_slots() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let genesis_block = Arc::new(genesis::beacon_block(&genesis_state));
+        let genesis_block_root = genesis_block.message().hash_tree_root();
+
+        let store = Store::new(
+            config.clone_arc(),
+            StoreConfig::default(),
+            genesis_block,
+            genesis_state,
+            true,
+        );
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache.clone_arc());
+        let slots = [9, 3, 5, 3];
+
+        block_processor.prewarm_slots(&store, genesis_block_root, &slots)?;
+
+        for slot in slots {
+            let (state, _) =
+                state_cache.get_or_insert_with(genesis_block_root, slot, true, || {
+                    bail!("state at slot {slot} should have been prewarmed")
+                })?;
+
+            assert_eq!(state.slot(), slot);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn replay_block_applies_block_to_state_with_given_root() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let genesis_block = Arc::new(genesis::beacon_block(&genesis_state));
+        let genesis_state_root = genesis_state.hash_tree_root();
+
+        let (block_1, state_1) =
+            factory::empty_block(&config, genesis_state.clone_arc(), 1, H256::zero())?;
+
+        let store = Store::new(
+            config.clone_arc(),
+            StoreConfig::default(),
+            genesis_block,
+            genesis_state,
+            true,
+        );
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache.clone_arc());
+
+        let replayed_state = block_processor.replay_block(
+            &store,
+            genesis_state_root,
+            &block_1,
+            NullExecutionEngine,
+            NullVerifier,
+        )?;
+
+        assert_eq!(replayed_state.hash_tree_root(), state_1.hash_tree_root());
+        assert_eq!(state_cache.len()?, 0);
+
+        block_processor
+            .replay_block(
+                &store,
+                H256::repeat_byte(1),
+                &block_1,
+                NullExecutionEngine,
+                NullVerifier,
+            )
+            .expect_err("replaying against an unknown state should fail");
+
+        Ok(())
+    }
+
+    #[test]
+    fn replay_block_finds_states_on_non_canonical_forks() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let genesis_block = Arc::new(genesis::beacon_block(&genesis_state));
+
+        let mut store = Store::new(
+            config.clone_arc(),
+            StoreConfig::default(),
+            genesis_block,
+            genesis_state.clone_arc(),
+            true,
+        );
+
+        store.apply_tick(Tick::start_of_slot(1))?;
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config.clone_arc(), state_cache);
+
+        // Only one of the competing blocks can be in the canonical chain.
+        for byte in 1..=2 {
+            let (block_1, state_1) = factory::empty_block(
+                &config,
+                genesis_state.clone_arc(),
+                1,
+                H256::repeat_byte(byte),
+            )?;
+
+            let block_action = block_processor.validate_block(
+                &store,
+                &block_1,
+                StateRootPolicy::Verify,
+                NullExecutionEngine,
+                NullVerifier,
+            )?;
+
+            let BlockAction::Accept(chain_link, _) = block_action else {
+                bail!("block should be accepted");
+            };
+
+            store.apply_block(chain_link)?;
+
+            let (block_2, state_2) =
+                factory::empty_block(&config, state_1.clone_arc(), 2, H256::zero())?;
+
+            let replayed_state = block_processor.replay_block(
+                &store,
+                state_1.hash_tree_root(),
+                &block_2,
+                NullExecutionEngine,
+                NullVerifier,
+            )?;
+
+            assert_eq!(replayed_state.hash_tree_root(), state_2.hash_tree_root());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn replay_block_finds_states_with_empty_slots_in_state_cache() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let genesis_block = Arc::new(genesis::beacon_block(&genesis_state));
+        let genesis_block_root = genesis_block.message().hash_tree_root();
+
+        let (block_3, state_3) =
+            factory::empty_block(&config, genesis_state.clone_arc(), 3, H256::zero())?;
+
+        let store = Store::new(
+            config.clone_arc(),
+            StoreConfig::default(),
+            genesis_block,
+            genesis_state,
+            true,
+        );
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache.clone_arc());
+
+        block_processor.prewarm_state(&store, genesis_block_root, 3)?;
+
+        let (pre_state, _) = state_cache.get_or_insert_with(genesis_block_root, 3, true, || {
+            bail!("state should have been prewarmed")
+        })?;
+
+        let replayed_state = block_processor.replay_block(
+            &store,
+            pre_state.hash_tree_root(),
+            &block_3,
+            NullExecutionEngine,
+            NullVerifier,
+        )?;
+
+        assert_eq!(replayed_state.hash_tree_root(), state_3.hash_tree_root());
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_block_ephemeral_does_not_modify_state_cache() -> Result<()> {
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let genesis_block = Arc::new(genesis::beacon_block(&genesis_state));
+
+        let (block_1, _) =
+            factory::empty_block(&config, genesis_state.clone_arc(), 1, H256::zero())?;
+
+        let mut store = Store::new(
+            config.clone_arc(),
+            StoreConfig::default(),
+            genesis_block,
+            genesis_state,
+            true,
+        );
+
+        store.apply_tick(Tick::start_of_slot(1))?;
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache.clone_arc());
+
+        let block_action = block_processor.validate_block_ephemeral(
+            &store,
+            &block_1,
+            StateRootPolicy::Verify,
+            NullExecutionEngine,
+            NullVerifier,
+        )?;
+
+        assert!(matches!(block_action, BlockAction::Accept(_, _)));
+        assert_eq!(state_cache.len()?, 0);
+        assert_eq!(block_processor.highest_processed_slot(), 0);
+
+        let block_action = block_processor.validate_block(
+            &store,
+            &block_1,
+            StateRootPolicy::Verify,
+            NullExecutionEngine,
+            NullVerifier,
+        )?;
+
+        assert!(matches!(block_action, BlockAction::Accept(_, _)));
+        assert_eq!(state_cache.len()?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_blocks_for_gossip_matches_individual_validation() -> Result<()> {
+        fn describe(result: &Result<Option<BlockAction<Minimal>>>) -> &'static str {
+            match result {
+                Ok(None | Some(BlockAction::Accept(_, _))) => "accept",
+                Ok(Some(BlockAction::Ignore(_))) => "ignore",
+                Ok(Some(BlockAction::DelayUntilSlot(_))) => "delay until slot",
+                Ok(Some(_)) => "other action",
+                Err(_) => "error",
+            }
+        }
+
+        let config = Arc::new(Config::minimal());
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let genesis_block = Arc::new(genesis::beacon_block(&genesis_state));
+
+        let mut blocks = (1..=3)
+            .map(|byte| {
+                let graffiti = H256::repeat_byte(byte);
+                let (block, _) =
+                    factory::empty_block(&config, genesis_state.clone_arc(), 1, graffiti)?;
+                Ok(block)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (future_block, _) =
+            factory::empty_block(&config, genesis_state.clone_arc(), 2, H256::zero())?;
+
+        blocks.push(future_block);
+
+        let mut store = Store::new(
+            config.clone_arc(),
+            StoreConfig::default(),
+            genesis_block,
+            genesis_state,
+            true,
+        );
+
+        store.apply_tick(Tick::start_of_slot(1))?;
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache.clone_arc());
+
+        let batch_results = block_processor
+            .validate_blocks_for_gossip(&store, &blocks)
+            .iter()
+            .map(describe)
+            .collect_vec();
+
+        let individual_results = blocks
+            .iter()
+            .map(|block| describe(&block_processor.validate_block_for_gossip(&store, block)))
+            .collect_vec();
+
+        assert_eq!(
+            batch_results,
+            ["accept", "accept", "accept", "delay until slot"],
+        );
+        assert_eq!(batch_results, individual_results);
+        assert_eq!(state_cache.len()?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn attestation_rewards_match_rewards_from_full_block_processing() -> Result<()> {
+        let config = Arc::new(Config::minimal().start_and_stay_in(Phase::Altair));
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let blocks = factory::full_blocks_up_to_epoch(&config, genesis_state.clone_arc(), 1)?;
+        let (block, _) = blocks[0].as_ref().clone().split();
+
+        let mut state = genesis_state;
+        combined::process_slots(&config, state.make_mut(), block.slot())?;
+
+        let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+        let block_processor = BlockProcessor::new(config, state_cache);
+
+        let attestation_rewards = block_processor.attestation_rewards(state.clone_arc(), &block)?;
+        let (_, block_rewards) =
+            block_processor.process_trusted_block_with_report(state, &block)?;
+        let block_rewards = block_rewards.expect("block rewards should be computed");
+
+        assert_ne!(block_rewards.attestations, 0);
+        assert_eq!(
+            attestation_rewards.iter().sum::<Gwei>(),
+            block_rewards.attestations,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_rewards_match_sum_of_block_rewards() -> Result<()> {
+        let config = Arc::new(Config::minimal().start_and_stay_in(Phase::Altair));
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let blocks = factory::full_blocks_up_to_epoch(&config, genesis_state.clone_arc(), 1)?
+            .into_iter()
+            .map(|block| block.as_ref().clone().split().0)
+            .collect_vec();
+
+        let new_block_processor = || {
+            let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+            BlockProcessor::new(config.clone_arc(), state_cache)
+        };
+
+        let mut expected = AggregateRewards::default();
+        let mut state = genesis_state.clone_arc();
+        let block_processor = new_block_processor();
+
+        for block in &blocks {
+            combined::process_slots(&config, state.make_mut(), block.slot())?;
+
+            let (post_state, block_rewards) =
+                block_processor.process_trusted_block_with_report(state, block)?;
+
+            let block_rewards = block_rewards.expect("block rewards should be computed");
+
+            expected.blocks += 1;
+            expected.rewards.total += block_rewards.total;
+            expected.rewards.attestations += block_rewards.attestations;
+            expected.rewards.sync_aggregate += block_rewards.sync_aggregate;
+            state = post_state;
+        }
+
+        let (_, actual) = new_block_processor()
+            .process_trusted_blocks_with_aggregate_rewards(genesis_state, &blocks)?;
+
+        assert_eq!(actual.blocks, blocks.len().try_into()?);
+        assert_ne!(actual.rewards.total, 0);
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_rewards_are_not_modified_on_overflow() -> Result<()> {
+        let mut aggregate_rewards = AggregateRewards::default();
+
+        let block_rewards = BlockRewards {
+            total: Gwei::MAX,
+            attestations: Gwei::MAX,
+            ..BlockRewards::default()
+        };
+
+        aggregate_rewards.add(block_rewards)?;
+
+        let expected = aggregate_rewards;
+
+        aggregate_rewards
+            .add(BlockRewards {
+                total: 1,
+                sync_aggregate: 1,
+                ..BlockRewards::default()
+            })
+            .expect_err("total should overflow");
+
+        assert_eq!(aggregate_rewards, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn phase_transition_is_detected_when_first_block_of_new_phase_is_processed() -> Result<()> {
+        let metrics = METRICS.get_or_init(|| {
+            Arc::new(Metrics::new().expect("metrics should be created successfully"))
+        });
+
+        let transitions = || {
+            metrics
+                .phase_transitions_count
+                .with_label_values(&[Phase::Deneb.as_ref()])
+                .get()
+        };
+
+        let config = Arc::new(Config::minimal().upgrade_once(Phase::Deneb, 1));
+        let (genesis_state, _) = factory::min_genesis_state::<Minimal>(&config)?;
+        let (capella_block, _) =
+            factory::empty_block(&config, genesis_state.clone_arc(), 1, H256::zero())?;
+        let (deneb_block, _) =
+            factory::empty_block(&config, genesis_state.clone_arc(), 8, H256::zero())?;
+        let new_block_processor = || {
+            let state_cache = Arc::new(StateCacheProcessor::new(Duration::from_secs(1)));
+            BlockProcessor::new(config.clone_arc(), state_cache)
+        };
+
+        assert_eq!(capella_block.phase(), Phase::Capella);
+        assert_eq!(deneb_block.phase(), Phase::Deneb);
+
+        // The transition should be detected even if the Deneb block is the first one processed.
+        for blocks in [
+            [&capella_block, &deneb_block].as_slice(),
+            [&deneb_block].as_slice(),
+        ] {
+            let block_processor = new_block_processor();
+
+            assert_eq!(block_processor.highest_processed_phase(), None);
+
+            // Other tests may process Deneb blocks concurrently, so only check lower bounds.
+            let transitions_before = transitions();
+
+            for block in blocks {
+                block_processor.perform_state_transition(
+                    genesis_state.clone_arc(),
+                    block,
+                    block.message().hash_tree_root(),
+                    ProcessSlots::IfNeeded,
+                    StateRootPolicy::Verify,
+                    NullExecutionEngine,
+                    NullVerifier,
+                    NullSlotReport,
+                )?;
+            }
+
+            assert!(transitions() > transitions_before);
+            assert_eq!(
+                block_processor.highest_processed_phase(),
+                Some(Phase::Deneb)
+            );
+        }
+
+        let block_processor = new_block_processor();
+
+        assert!(!block_processor.record_processed_phase(1, Phase::Capella, Phase::Capella));
+        assert!(block_processor.record_processed_phase(8, Phase::Capella, Phase::Deneb));
+        assert!(!block_processor.record_processed_phase(8, Phase::Capella, Phase::Deneb));
+        assert!(!block_processor.record_processed_phase(2, Phase::Capella, Phase::Capella));
+        assert_eq!(
+            block_processor.highest_processed_phase(),
+            Some(Phase::Deneb)
+        );
+
+        Ok(())
+    }
+}
@@ -16,6 +16,7 @@
 //! [`storage`]: ::storage
 
 pub use crate::{
+    block_processor::{AggregateRewards, ProcessedBlockEvent, PROCESSED_BLOCK_EVENT_CAPACITY},
     controller::Controller,
     messages::{
         ApiMessage, AttestationVerifierMessage, BlockEvent, ChainReorgEvent,
@@ -596,6 +596,7 @@ pub enum BlobSidecarAction<P: Preset> {
     DelayUntilSlot(Arc<BlobSidecar<P>>),
 }
 
+#[derive(Clone, Copy)]
 pub enum PartialBlockAction {
     Accept,
     Ignore,
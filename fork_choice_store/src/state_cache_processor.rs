@@ -1,9 +1,11 @@
 use core::time::Duration;
-use std::{backtrace::Backtrace, sync::Arc};
+use std::{backtrace::Backtrace, collections::VecDeque, sync::Arc, time::Instant};
 
 use anyhow::{bail, Result};
 use features::Feature;
 use log::warn;
+use parking_lot::Mutex;
+use prometheus_metrics::METRICS;
 use state_cache::{StateCache, StateWithRewards};
 use std_ext::ArcExt as _;
 use tap::Pipe as _;
@@ -18,8 +20,13 @@ use types::{
 
 use crate::Store;
 
+// 5 epochs on Mainnet (6.4 minutes each). Long enough to cover several epochs of pruning.
+const EVICTION_RATE_WINDOW: Duration = Duration::from_secs(32 * 60);
+
 pub struct StateCacheProcessor<P: Preset> {
     state_cache: StateCache<P>,
+    // Times at which states were pruned and how many were pruned each time.
+    evictions: Mutex<VecDeque<(Instant, usize)>>,
 }
 
 impl<P: Preset> StateCacheProcessor<P> {
@@ -27,6 +34,7 @@ impl<P: Preset> StateCacheProcessor<P> {
     pub fn new(state_cache_lock_timeout: Duration) -> Self {
         Self {
             state_cache: StateCache::new(state_cache_lock_timeout),
+            evictions: Mutex::new(VecDeque::new()),
         }
     }
 
@@ -61,8 +69,36 @@ impl<P: Preset> StateCacheProcessor<P> {
         ignore_missing_rewards: bool,
         f: impl FnOnce() -> Result<StateWithRewards<P>>,
     ) -> Result<StateWithRewards<P>> {
-        self.state_cache
-            .get_or_insert_with(block_root, slot, ignore_missing_rewards, f)
+        let start = Instant::now();
+        let mut compute_time = None;
+
+        let result =
+            self.state_cache
+                .get_or_insert_with(block_root, slot, ignore_missing_rewards, || {
+                    let compute_start = Instant::now();
+                    let result = f();
+                    compute_time = Some(compute_start.elapsed());
+                    result
+                });
+
+        // Overly long waits with short compute times point to lock contention.
+        if let Some(metrics) = METRICS.get() {
+            let wait_time = start
+                .elapsed()
+                .saturating_sub(compute_time.unwrap_or_default());
+
+            metrics
+                .state_cache_wait_times
+                .observe(wait_time.as_secs_f64());
+
+            if let Some(compute_time) = compute_time {
+                metrics
+                    .state_cache_compute_times
+                    .observe(compute_time.as_secs_f64());
+            }
+        }
+
+        result
     }
 
     pub fn len(&self) -> Result<usize> {
@@ -70,7 +106,37 @@ impl<P: Preset> StateCacheProcessor<P> {
     }
 
     pub fn prune(&self, last_pruned_slot: Slot) -> Result<()> {
-        self.state_cache.prune(last_pruned_slot)
+        let pruned = self.state_cache.prune(last_pruned_slot)?;
+
+        if let Some(metrics) = METRICS.get() {
+            metrics
+                .state_cache_evictions_count
+                .inc_by(pruned.try_into()?);
+        }
+
+        let now = Instant::now();
+        let mut evictions = self.evictions.lock();
+
+        evictions.push_back((now, pruned));
+        prune_evictions(&mut evictions, now);
+
+        Ok(())
+    }
+
+    /// Returns the average number of states evicted per minute over the last 32 minutes.
+    ///
+    /// A high sustained rate means states are evicted only to be computed again,
+    /// which suggests the cache is too small for the workload.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, clippy::float_arithmetic)]
+    pub fn eviction_rate(&self) -> f64 {
+        let mut evictions = self.evictions.lock();
+
+        prune_evictions(&mut evictions, Instant::now());
+
+        let evicted = evictions.iter().map(|(_, pruned)| pruned).sum::<usize>();
+
+        evicted as f64 * 60.0 / EVICTION_RATE_WINDOW.as_secs_f64()
     }
 
     pub fn try_state_at_slot(
@@ -207,6 +273,15 @@ fn process_slots<P: Preset>(
     Ok(state)
 }
 
+fn prune_evictions(evictions: &mut VecDeque<(Instant, usize)>, now: Instant) {
+    while evictions
+        .front()
+        .is_some_and(|(instant, _)| now.duration_since(*instant) > EVICTION_RATE_WINDOW)
+    {
+        evictions.pop_front();
+    }
+}
+
 fn should_print_slot_processing_warning<P: Preset>(store: &Store<P>) -> bool {
     Feature::WarnOnStateCacheSlotProcessing.is_enabled() && store.is_forward_synced()
 }
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use anyhow::{ensure, Result};
-use execution_engine::ExecutionEngine;
+use execution_engine::{ExecutionEngine, PowBlockLookup};
 use helper_functions::misc;
 use std_ext::ArcExt as _;
 use types::{
@@ -62,14 +62,24 @@ pub fn validate_merge_block<P: Preset, E: ExecutionEngine<P>>(
             PartialBlockAction::Ignore
         };
 
+    // An execution client that does not respond in time may not be synced or may be broken.
+    // Accepting blocks optimistically because of that would hide the problem.
+    let lookup = |block_hash| match execution_engine.pow_block(block_hash) {
+        PowBlockLookup::Found(pow_block) => Ok(pow_block),
+        PowBlockLookup::NotFound => Err(pow_block_missing_block_action),
+        PowBlockLookup::TimedOut => Err(PartialBlockAction::Ignore),
+    };
+
     // > Check if `pow_block` is available
-    let Some(pow_block) = execution_engine.pow_block(body.execution_payload().parent_hash()) else {
-        return Ok(pow_block_missing_block_action);
+    let pow_block = match lookup(body.execution_payload().parent_hash()) {
+        Ok(pow_block) => pow_block,
+        Err(action) => return Ok(action),
     };
 
     // > Check if `pow_parent` is available
-    let Some(pow_parent) = execution_engine.pow_block(pow_block.pow_block.parent_hash) else {
-        return Ok(pow_block_missing_block_action);
+    let pow_parent = match lookup(pow_block.pow_block.parent_hash) {
+        Ok(pow_parent) => pow_parent,
+        Err(action) => return Ok(action),
     };
 
     // > Check if `pow_block` is a valid terminal PoW block
@@ -38,8 +38,8 @@ use reqwest::{header::HeaderValue, Url};
 use runtime::{
     MetricsConfig, StorageConfig, DEFAULT_ETH1_DB_SIZE, DEFAULT_ETH2_DB_SIZE,
     DEFAULT_LIBP2P_IPV4_PORT, DEFAULT_LIBP2P_IPV6_PORT, DEFAULT_LIBP2P_QUIC_IPV4_PORT,
-    DEFAULT_LIBP2P_QUIC_IPV6_PORT, DEFAULT_METRICS_PORT, DEFAULT_REQUEST_TIMEOUT,
-    DEFAULT_TARGET_PEERS, DEFAULT_TARGET_SUBNET_PEERS, DEFAULT_TIMEOUT,
+    DEFAULT_LIBP2P_QUIC_IPV6_PORT, DEFAULT_METRICS_PORT, DEFAULT_REQUEST_CONNECT_TIMEOUT,
+    DEFAULT_REQUEST_TIMEOUT, DEFAULT_TARGET_PEERS, DEFAULT_TARGET_SUBNET_PEERS, DEFAULT_TIMEOUT,
 };
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
@@ -293,6 +293,20 @@ struct BeaconNodeOptions {
     #[clap(long, default_value_t = DEFAULT_REQUEST_TIMEOUT)]
     request_timeout: u64,
 
+    /// Default global timeout for establishing connections in milliseconds
+    #[clap(long, default_value_t = DEFAULT_REQUEST_CONNECT_TIMEOUT)]
+    request_connect_timeout: u64,
+
+    /// How long idle connections are kept open for reuse in milliseconds
+    /// [default: 90000]
+    #[clap(long)]
+    request_pool_idle_timeout: Option<u64>,
+
+    /// Max number of idle connections kept open for reuse per host
+    /// [default: unlimited]
+    #[clap(long)]
+    request_pool_max_idle_per_host: Option<usize>,
+
     /// Default state cache lock timeout in milliseconds
     #[clap(long, default_value_t = DEFAULT_CACHE_LOCK_TIMEOUT_MILLIS)]
     state_cache_lock_timeout: u64,
@@ -864,6 +878,9 @@ impl GrandineArgs {
             prune_storage,
             unfinalized_states_in_memory,
             request_timeout,
+            request_connect_timeout,
+            request_pool_idle_timeout,
+            request_pool_max_idle_per_host,
             state_cache_lock_timeout,
             state_slot,
             subscribe_all_subnets,
@@ -1216,6 +1233,9 @@ impl GrandineArgs {
             storage_config,
             unfinalized_states_in_memory,
             request_timeout: Duration::from_millis(request_timeout),
+            request_connect_timeout: Duration::from_millis(request_connect_timeout),
+            request_pool_idle_timeout: request_pool_idle_timeout.map(Duration::from_millis),
+            request_pool_max_idle_per_host,
             state_cache_lock_timeout: Duration::from_millis(state_cache_lock_timeout),
             command,
             slashing_enabled,
@@ -1843,6 +1863,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn request_pool_options() {
+        let config = config_from_args([
+            "--request-pool-idle-timeout",
+            "30000",
+            "--request-pool-max-idle-per-host",
+            "4",
+        ]);
+
+        assert_eq!(
+            config.request_pool_idle_timeout,
+            Some(Duration::from_secs(30)),
+        );
+        assert_eq!(config.request_pool_max_idle_per_host, Some(4));
+    }
+
     fn config_from_args<'a>(arguments: impl IntoIterator<Item = &'a str>) -> GrandineConfig {
         try_config_from_args(arguments)
             .expect("GrandineArgs should be successfully parsed from arguments")
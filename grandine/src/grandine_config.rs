@@ -44,6 +44,9 @@ pub struct GrandineConfig {
     pub storage_config: StorageConfig,
     pub unfinalized_states_in_memory: u64,
     pub request_timeout: Duration,
+    pub request_connect_timeout: Duration,
+    pub request_pool_idle_timeout: Option<Duration>,
+    pub request_pool_max_idle_per_host: Option<usize>,
     pub state_cache_lock_timeout: Duration,
     pub command: Option<GrandineCommand>,
     pub slashing_enabled: bool,
@@ -348,6 +348,9 @@ fn try_main() -> Result<()> {
         network_config,
         storage_config,
         request_timeout,
+        request_connect_timeout,
+        request_pool_idle_timeout,
+        request_pool_max_idle_per_host,
         state_cache_lock_timeout,
         unfinalized_states_in_memory,
         command,
@@ -412,10 +415,24 @@ fn try_main() -> Result<()> {
     // Creating multiple `reqwest::Client`s seems to leak memory.
     // See <https://github.com/seanmonstar/reqwest/issues?q=is%3Aissue+memory>.
     // Create a single one for the whole application and reuse it through `Signer::client`.
-    let client = ClientBuilder::new()
+    //
+    // Connections are kept alive and reused across requests to the same host.
+    // This matters most for Engine API calls, which would otherwise pay for a new connection
+    // (and a TLS handshake with remote execution clients) on the critical path.
+    let mut client_builder = ClientBuilder::new()
         .timeout(request_timeout)
-        .user_agent(APPLICATION_VERSION_WITH_PLATFORM)
-        .build()?;
+        .connect_timeout(request_connect_timeout)
+        .user_agent(APPLICATION_VERSION_WITH_PLATFORM);
+
+    if let Some(idle_timeout) = request_pool_idle_timeout {
+        client_builder = client_builder.pool_idle_timeout(idle_timeout);
+    }
+
+    if let Some(max_idle_per_host) = request_pool_max_idle_per_host {
+        client_builder = client_builder.pool_max_idle_per_host(max_idle_per_host);
+    }
+
+    let client = client_builder.build()?;
 
     let mut cache = use_validator_key_cache.then(|| {
         ValidatorKeyCache::new(
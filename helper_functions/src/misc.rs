@@ -287,9 +287,21 @@ pub fn compute_timestamp_at_slot<P: Preset>(
     config: &Config,
     state: &(impl BeaconState<P> + ?Sized),
     slot: Slot,
+) -> UnixSeconds {
+    compute_timestamp_at_slot_from_genesis_time(config, state.genesis_time(), slot)
+}
+
+/// Like [`compute_timestamp_at_slot`], but takes the genesis time instead of a state.
+///
+/// Useful for computing timestamps of future proposals when no state is at hand.
+#[must_use]
+pub fn compute_timestamp_at_slot_from_genesis_time(
+    config: &Config,
+    genesis_time: UnixSeconds,
+    slot: Slot,
 ) -> UnixSeconds {
     let slots_since_genesis = slot - GENESIS_SLOT;
-    state.genesis_time() + slots_since_genesis * config.seconds_per_slot.get()
+    genesis_time + slots_since_genesis * config.seconds_per_slot.get()
 }
 
 #[must_use]
@@ -476,6 +488,22 @@ mod tests {
         assert_eq!(compute_epoch_at_slot::<Minimal>(7), 0);
     }
 
+    #[test]
+    fn test_timestamp_at_slot_from_genesis_time() {
+        let config = Config::mainnet();
+        let genesis_time = 1_606_824_023;
+
+        assert_eq!(
+            compute_timestamp_at_slot_from_genesis_time(&config, genesis_time, GENESIS_SLOT),
+            genesis_time,
+        );
+
+        assert_eq!(
+            compute_timestamp_at_slot_from_genesis_time(&config, genesis_time, 4_700_013),
+            1_663_224_179,
+        );
+    }
+
     #[test]
     fn test_start_slot_at_epoch() {
         assert_eq!(compute_start_slot_at_epoch::<Minimal>(1), 8);
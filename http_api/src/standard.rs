@@ -2893,6 +2893,33 @@ mod tests {
         Ok(())
     }
 
+    // See the example response for `getBlockRewards` in the Eth Beacon Node API specification.
+    #[test]
+    fn test_serialize_block_rewards_response() -> Result<()> {
+        let rewards_response = BlockRewardsResponse {
+            proposer_index: 123,
+            total: 2_000,
+            attestations: 1_500,
+            sync_aggregate: 300,
+            proposer_slashings: 100,
+            attester_slashings: 100,
+        };
+
+        assert_eq!(
+            serde_json::to_value(rewards_response)?,
+            json!({
+                "proposer_index": "123",
+                "total": "2000",
+                "attestations": "1500",
+                "sync_aggregate": "300",
+                "proposer_slashings": "100",
+                "attester_slashings": "100",
+            }),
+        );
+
+        Ok(())
+    }
+
     async fn extract_query<T: DeserializeOwned + 'static>(query: impl Display + Send) -> Result<T> {
         let (mut parts, ()) = Request::get(format!("/?{query}")).body(())?.into_parts();
 
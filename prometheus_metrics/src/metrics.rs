@@ -130,12 +130,18 @@ pub struct Metrics {
     pub beacon_proposer_index_init_count: IntCounter,
     pub total_active_balance_init_count: IntCounter,
     pub validator_indices_init_count: IntCounter,
+    pub state_cache_wait_times: Histogram,
+    pub state_cache_compute_times: Histogram,
+    pub state_cache_evictions_count: IntCounter,
 
     // Transition function metrics
     pub blinded_block_transition_times: Histogram,
     pub block_transition_times: Histogram,
     pub epoch_processing_times: Histogram,
     pub process_slot_times: Histogram,
+    pub phase_transitions_count: IntCounterVec,
+    pub blinded_blocks_processed: IntCounter,
+    pub full_blocks_processed: IntCounter,
 
     // EF interop metrics
     beacon_current_active_validators: IntGauge,
@@ -164,6 +170,11 @@ pub struct Metrics {
     pub eth1_api_request_times: HistogramVec,
     pub eth1_api_errors_count: IntCounter,
     pub eth1_api_reset_count: IntCounter,
+    pub eth1_api_endpoint_switches_count: IntCounterVec,
+    pub eth1_api_endpoints_tried_last: IntGauge,
+    pub eth1_api_engine_method_versions: IntCounterVec,
+    pub eth1_api_payload_statuses: IntCounterVec,
+    pub eth1_api_new_payload_divergences_count: IntCounter,
 
     // Jemalloc stats
     pub jemalloc_bytes_allocated: IntGauge,
@@ -562,6 +573,21 @@ impl Metrics {
                 "Validator indices cache init count",
             )?,
 
+            state_cache_wait_times: Histogram::with_opts(histogram_opts!(
+                "STATE_CACHE_WAIT_TIMES",
+                "Time spent in state cache lookups and locks, excluding computing missing states",
+            ))?,
+
+            state_cache_compute_times: Histogram::with_opts(histogram_opts!(
+                "STATE_CACHE_COMPUTE_TIMES",
+                "Time spent computing states missing from the state cache",
+            ))?,
+
+            state_cache_evictions_count: IntCounter::new(
+                "STATE_CACHE_EVICTIONS_COUNT",
+                "Number of states pruned from the state cache",
+            )?,
+
             // Transition function metrics
             blinded_block_transition_times: Histogram::with_opts(histogram_opts!(
                 "BLINDED_BLOCK_TRANSITION_TIMES",
@@ -583,6 +609,24 @@ impl Metrics {
                 "Transition function empty slots processing times",
             ))?,
 
+            phase_transitions_count: IntCounterVec::new(
+                opts!(
+                    "PHASE_TRANSITIONS_COUNT",
+                    "Number of times the first block of a new phase was processed",
+                ),
+                &["phase"],
+            )?,
+
+            blinded_blocks_processed: IntCounter::new(
+                "BLINDED_BLOCKS_PROCESSED",
+                "Number of blinded blocks processed",
+            )?,
+
+            full_blocks_processed: IntCounter::new(
+                "FULL_BLOCKS_PROCESSED",
+                "Number of full blocks processed",
+            )?,
+
             // EF interop metrics
             beacon_current_active_validators: IntGauge::new(
                 "beacon_current_active_validators",
@@ -680,6 +724,40 @@ impl Metrics {
                 "Number of ETH1 API errors",
             )?,
 
+            eth1_api_endpoint_switches_count: IntCounterVec::new(
+                opts!(
+                    "eth1_endpoint_switches_total",
+                    "Number of times ETH1 API switched to a fallback endpoint by endpoint index",
+                ),
+                &["from", "to"],
+            )?,
+
+            eth1_api_endpoints_tried_last: IntGauge::new(
+                "eth1_endpoints_tried_last",
+                "Number of ETH1 API endpoints tried by the most recent request",
+            )?,
+
+            eth1_api_engine_method_versions: IntCounterVec::new(
+                opts!(
+                    "eth1_engine_method_versions_total",
+                    "Number of successful Engine API calls by method and version",
+                ),
+                &["method", "version"],
+            )?,
+
+            eth1_api_payload_statuses: IntCounterVec::new(
+                opts!(
+                    "eth1_new_payload_statuses_total",
+                    "Number of engine_newPayload responses by payload status",
+                ),
+                &["status"],
+            )?,
+
+            eth1_api_new_payload_divergences_count: IntCounter::new(
+                "eth1_new_payload_divergences_total",
+                "Number of payloads that one execution client considered valid and another invalid",
+            )?,
+
             // Jemalloc stats
             jemalloc_bytes_allocated: IntGauge::new(
                 "JEMALLOC_BYTES_ALLOCATED",
@@ -827,10 +905,16 @@ impl Metrics {
         default_registry.register(Box::new(self.beacon_proposer_index_init_count.clone()))?;
         default_registry.register(Box::new(self.total_active_balance_init_count.clone()))?;
         default_registry.register(Box::new(self.validator_indices_init_count.clone()))?;
+        default_registry.register(Box::new(self.state_cache_wait_times.clone()))?;
+        default_registry.register(Box::new(self.state_cache_compute_times.clone()))?;
+        default_registry.register(Box::new(self.state_cache_evictions_count.clone()))?;
         default_registry.register(Box::new(self.blinded_block_transition_times.clone()))?;
         default_registry.register(Box::new(self.block_transition_times.clone()))?;
         default_registry.register(Box::new(self.epoch_processing_times.clone()))?;
         default_registry.register(Box::new(self.process_slot_times.clone()))?;
+        default_registry.register(Box::new(self.phase_transitions_count.clone()))?;
+        default_registry.register(Box::new(self.blinded_blocks_processed.clone()))?;
+        default_registry.register(Box::new(self.full_blocks_processed.clone()))?;
         default_registry.register(Box::new(self.beacon_current_active_validators.clone()))?;
         default_registry.register(Box::new(self.beacon_current_justified_epoch.clone()))?;
         default_registry.register(Box::new(self.beacon_finalized_epoch.clone()))?;
@@ -857,6 +941,13 @@ impl Metrics {
         default_registry.register(Box::new(self.eth1_api_request_times.clone()))?;
         default_registry.register(Box::new(self.eth1_api_errors_count.clone()))?;
         default_registry.register(Box::new(self.eth1_api_reset_count.clone()))?;
+        default_registry.register(Box::new(self.eth1_api_endpoint_switches_count.clone()))?;
+        default_registry.register(Box::new(self.eth1_api_endpoints_tried_last.clone()))?;
+        default_registry.register(Box::new(self.eth1_api_engine_method_versions.clone()))?;
+        default_registry.register(Box::new(self.eth1_api_payload_statuses.clone()))?;
+        default_registry.register(Box::new(
+            self.eth1_api_new_payload_divergences_count.clone(),
+        ))?;
         default_registry.register(Box::new(self.jemalloc_bytes_allocated.clone()))?;
         default_registry.register(Box::new(self.jemalloc_bytes_active.clone()))?;
         default_registry.register(Box::new(self.jemalloc_bytes_metadata.clone()))?;
@@ -969,6 +1060,12 @@ impl Metrics {
             .set(thread_count as i64)
     }
 
+    // Eth1 API
+    pub fn set_eth1_api_endpoints_tried_last(&self, endpoints_tried: usize) {
+        self.eth1_api_endpoints_tried_last
+            .set(endpoints_tried as i64)
+    }
+
     // Network / Gossip stats
     pub fn register_gossip_object(&self, labels: &[&str]) {
         match self.gossip_objects.get_metric_with_label_values(labels) {
@@ -2,8 +2,9 @@ pub use crate::{
     defaults::{
         default_network_config, DEFAULT_ETH1_DB_SIZE, DEFAULT_ETH2_DB_SIZE,
         DEFAULT_LIBP2P_IPV4_PORT, DEFAULT_LIBP2P_IPV6_PORT, DEFAULT_LIBP2P_QUIC_IPV4_PORT,
-        DEFAULT_LIBP2P_QUIC_IPV6_PORT, DEFAULT_METRICS_PORT, DEFAULT_REQUEST_TIMEOUT,
-        DEFAULT_TARGET_PEERS, DEFAULT_TARGET_SUBNET_PEERS, DEFAULT_TIMEOUT,
+        DEFAULT_LIBP2P_QUIC_IPV6_PORT, DEFAULT_METRICS_PORT, DEFAULT_REQUEST_CONNECT_TIMEOUT,
+        DEFAULT_REQUEST_TIMEOUT, DEFAULT_TARGET_PEERS, DEFAULT_TARGET_SUBNET_PEERS,
+        DEFAULT_TIMEOUT,
     },
     misc::{MetricsConfig, StorageConfig},
     runtime::run_after_genesis,
@@ -148,6 +148,31 @@ impl<D: ArrayLength<H256>> MerkleTree<D> {
         hash
     }
 
+    /// Computes the root of a tree containing the first `chunk_count` chunks without adding any.
+    ///
+    /// This is the same algorithm as `get_deposit_root` in the deposit contract minus the length
+    /// mix-in. `chunk_count` must not exceed the number of chunks that have been pushed.
+    /// The root of a full tree cannot be computed this way because the hash of the last subtree is
+    /// never stored.
+    #[must_use]
+    pub fn root(&self, chunk_count: usize) -> H256 {
+        assert!(chunk_count < 1 << D::USIZE);
+
+        let mut hash = H256::zero();
+
+        // The suggested rewrite is not an improvement.
+        #[allow(clippy::needless_range_loop)]
+        for height in 0..D::USIZE {
+            if chunk_count.get_bit(height) {
+                hash = hashing::hash_256_256(self.sibling_hashes[height], hash);
+            } else {
+                hash = hashing::hash_256_256(hash, ZERO_HASHES[height]);
+            }
+        }
+
+        hash
+    }
+
     // See other implementations:
     // - <https://github.com/ethereum/research/blob/88eb03288a6ccd2f3e1f4b6bfd785bc52d10697b/spec_pythonizer/utils/merkle_minimal.py>
     // - <https://github.com/ethereum/research/blob/a4a600f2869feed5bfaab24b13ca1692069ef312/beacon_chain_impl/progressive_merkle_tree.py>
@@ -368,6 +393,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn merkle_tree_root_matches_push_and_compute_root() {
+        let mut merkle_tree = MerkleTree::<U3>::default();
+
+        assert_eq!(merkle_tree.root(0), ZERO_HASHES[3]);
+
+        for index in 0..7 {
+            let chunk = H256::repeat_byte(index.try_into().expect("index fits in u8"));
+            let expected_root = merkle_tree.push_and_compute_root(index, chunk);
+
+            assert_eq!(merkle_tree.root(index + 1), expected_root);
+        }
+    }
+
     #[test]
     fn depth_0_merkle_tree_extend_and_construct_proofs_handles_single_chunk() {
         itertools::assert_equal(
@@ -198,10 +198,14 @@ impl<P: Preset> StateCache<P> {
             .pipe(Ok)
     }
 
-    pub fn prune(&self, last_pruned_slot: Slot) -> Result<()> {
+    /// Returns the number of states removed.
+    pub fn prune(&self, last_pruned_slot: Slot) -> Result<usize> {
+        let mut pruned = 0;
+
         for (block_root, state_map_lock) in self.all_state_map_locks()? {
             let mut state_map = self.try_lock_map(&state_map_lock, block_root)?;
             let (_, retained) = state_map.split(&last_pruned_slot);
+            pruned += state_map.len() - retained.len();
             *state_map = retained;
         }
 
@@ -211,7 +215,7 @@ impl<P: Preset> StateCache<P> {
                 .is_some_and(|state_map| !state_map.is_empty())
         });
 
-        Ok(())
+        Ok(pruned)
     }
 
     fn all_state_map_locks(&self) -> Result<Vec<(H256, StateMapLock<P>)>> {
@@ -346,7 +350,7 @@ mod tests {
     fn test_state_cache_prune() -> Result<()> {
         let cache = new_test_cache()?;
 
-        cache.prune(2)?;
+        assert_eq!(cache.prune(2)?, 2);
 
         assert_eq!(cache.before_or_at_slot(ROOT_1, 1)?, None);
         assert_eq!(cache.before_or_at_slot(ROOT_2, 2)?, None);
@@ -488,7 +488,9 @@ pub fn process_trusted_block<P: Preset>(
     process_block(config, state, block, NullVerifier, slot_report)
 }
 
-fn process_block<P: Preset>(
+/// Like [`process_untrusted_block`] and [`process_trusted_block`], but lets the caller decide how
+/// signatures are verified.
+pub fn process_block<P: Preset>(
     config: &Config,
     state: &mut BeaconState<P>,
     block: &BeaconBlock<P>,
@@ -558,6 +560,59 @@ pub fn process_block_for_gossip<P: Preset>(
     }
 }
 
+/// Applies the attestations in `block` to `state` without processing the rest of the block.
+///
+/// The attestations are assumed to be valid.
+/// `state` must already be at the slot of `block`.
+///
+/// Phase 0 rewards attestations during epoch processing,
+/// so no attestation rewards are reported for Phase 0 blocks.
+pub fn apply_block_attestations<P: Preset>(
+    state: &mut BeaconState<P>,
+    block: &BeaconBlock<P>,
+    mut slot_report: impl SlotReport,
+) -> Result<()> {
+    match (state, block) {
+        (BeaconState::Phase0(state), BeaconBlock::Phase0(block)) => {
+            for attestation in &block.body.attestations {
+                phase0::apply_attestation(state, attestation)?;
+            }
+        }
+        (BeaconState::Altair(state), BeaconBlock::Altair(block)) => {
+            for attestation in &block.body.attestations {
+                altair::apply_attestation(state, attestation, &mut slot_report)?;
+            }
+        }
+        (BeaconState::Bellatrix(state), BeaconBlock::Bellatrix(block)) => {
+            for attestation in &block.body.attestations {
+                altair::apply_attestation(state, attestation, &mut slot_report)?;
+            }
+        }
+        (BeaconState::Capella(state), BeaconBlock::Capella(block)) => {
+            for attestation in &block.body.attestations {
+                altair::apply_attestation(state, attestation, &mut slot_report)?;
+            }
+        }
+        (BeaconState::Deneb(state), BeaconBlock::Deneb(block)) => {
+            for attestation in &block.body.attestations {
+                altair::apply_attestation(state, attestation, &mut slot_report)?;
+            }
+        }
+        (state, _) => {
+            // This match arm will silently match any new phases.
+            // Cause a compilation error if a new phase is added.
+            const_assert_eq!(Phase::CARDINALITY, 5);
+
+            bail!(PhaseError {
+                state_phase: state.phase(),
+                block_phase: block.phase(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
 pub fn process_untrusted_blinded_block<P: Preset>(
     config: &Config,
     state: &mut BeaconState<P>,
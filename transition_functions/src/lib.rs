@@ -84,7 +84,8 @@ pub mod phase0 {
     pub use epoch_processing::EpochReport;
 
     pub(crate) use block_processing::{
-        count_required_signatures, process_block, process_block_for_gossip, process_deposit_data,
+        apply_attestation, count_required_signatures, process_block, process_block_for_gossip,
+        process_deposit_data,
     };
     pub(crate) use epoch_intermediates::{statistics, StatisticsForTransition};
     pub(crate) use epoch_processing::{
@@ -249,7 +249,7 @@ fn process_attester_slashing<P: Preset>(
     Ok(())
 }
 
-fn apply_attestation<P: Preset>(
+pub fn apply_attestation<P: Preset>(
     state: &mut BeaconState<P>,
     attestation: &Attestation<P>,
 ) -> Result<()> {
@@ -868,6 +868,14 @@ impl<P: Preset> ExecutionPayload<P> {
             Self::Deneb(payload) => payload.block_hash,
         }
     }
+
+    pub const fn parent_hash(&self) -> ExecutionBlockHash {
+        match self {
+            Self::Bellatrix(payload) => payload.parent_hash,
+            Self::Capella(payload) => payload.parent_hash,
+            Self::Deneb(payload) => payload.parent_hash,
+        }
+    }
 }
 
 #[derive(From, Deserialize)]
@@ -893,7 +901,7 @@ impl<P: Preset> ExecutionPayloadHeader<P> {
 //                      entirely. Is this module the right place for it? See:
 //                      - <https://github.com/ethereum/consensus-specs/releases/tag/v1.4.0-alpha.0>
 //                      - <https://github.com/ethereum/consensus-specs/pull/3359>
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub enum ExecutionPayloadParams {
     Deneb {
         versioned_hashes: Vec<VersionedHash>,
@@ -17,8 +17,8 @@ use crate::{
     phase0::{
         consts::{FAR_FUTURE_EPOCH, GENESIS_EPOCH},
         primitives::{
-            ChainId, DomainType, Epoch, ExecutionAddress, ExecutionBlockHash, Gwei, NetworkId,
-            Slot, UnixSeconds, Version, H160, H32,
+            ChainId, DomainType, Epoch, ExecutionAddress, ExecutionBlockHash, ExecutionBlockNumber,
+            Gwei, NetworkId, Slot, UnixSeconds, Version, H160, H32,
         },
     },
     preset::{Preset, PresetName},
@@ -99,6 +99,11 @@ pub struct Config {
     #[serde(with = "serde_utils::string_or_native")]
     pub deposit_chain_id: ChainId,
     pub deposit_contract_address: ExecutionAddress,
+    /// The block the deposit contract was deployed in or any earlier one.
+    ///
+    /// Not part of standard configurations. Deposits are searched for starting from this block.
+    #[serde(with = "serde_utils::string_or_native")]
+    pub deposit_contract_deploy_block: ExecutionBlockNumber,
     #[serde(with = "serde_utils::string_or_native")]
     pub deposit_network_id: NetworkId,
 
@@ -194,6 +199,7 @@ impl Default for Config {
             // Deposit contract
             deposit_chain_id: 0,
             deposit_contract_address: ExecutionAddress::zero(),
+            deposit_contract_deploy_block: 0,
             deposit_network_id: 0,
 
             // Networking
@@ -250,6 +256,7 @@ impl Config {
             // Deposit contract
             deposit_chain_id: 1,
             deposit_contract_address: H160(hex!("00000000219ab540356cBB839Cbe05303d7705Fa")),
+            deposit_contract_deploy_block: 11_184_524,
             deposit_network_id: 1,
 
             // Transition
@@ -319,6 +326,7 @@ impl Config {
             // Deposit contract
             deposit_chain_id: 5,
             deposit_contract_address: H160(hex!("07b39F4fDE4A38bACe212b546dAc87C58DfE3fDC")),
+            deposit_contract_deploy_block: 3_743_587,
             deposit_network_id: 5,
 
             ..Self::default()
@@ -352,6 +360,7 @@ impl Config {
             // Deposit contract
             deposit_chain_id: 5,
             deposit_contract_address: H160(hex!("ff50ed3d0ec03aC01D4C79aAd74928BFF48a7b2b")),
+            deposit_contract_deploy_block: 4_367_322,
             deposit_network_id: 5,
 
             // Transition
@@ -390,6 +399,7 @@ impl Config {
             // Deposit contract
             deposit_chain_id: 11_155_111,
             deposit_contract_address: H160(hex!("7f02C3E3c98b133055B8B348B2Ac625669Ed295D")),
+            deposit_contract_deploy_block: 1_273_020,
             deposit_network_id: 11_155_111,
 
             // Transition